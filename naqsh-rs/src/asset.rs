@@ -1,5 +1,8 @@
 #![allow(dead_code)]
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
 
 use crate::image::Image;
 
@@ -8,9 +11,56 @@ enum Asset {
 }
 
 /// Assets Map.
+#[derive(Default)]
 pub struct Assets {
     assets: HashMap<String, Asset>,
     images: HashMap<String, Image>,
+    // Content hash of each loaded file, mapped to the assets loaded from it.
+    hashes: HashMap<u64, Vec<String>>,
+    // Content hash and bytes of the file each asset was loaded from.
+    sources: HashMap<String, (u64, Vec<u8>)>,
+}
+
+impl Assets {
+    /// Loads the file at `src` as asset `id`, decoding it with `decode`.
+    ///
+    /// A file whose bytes match those of an already loaded asset reuses that decoded image, even
+    /// under a different id, instead of being decoded again. Files are looked up by a hash of
+    /// their content, and the bytes are compared before reusing an image. Returns `None` if the
+    /// file can not be read or decoded.
+    pub fn load<D>(&mut self, id: &str, src: &Path, decode: D) -> Option<&Image>
+    where
+        D: FnOnce(&[u8]) -> Option<Image>
+    {
+        let data = std::fs::read(src).ok()?;
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let same = self.hashes.get(&hash).into_iter().flatten()
+            .find(|other| self.sources.get(*other).is_some_and(|(_, bytes)| *bytes == data));
+        let image = match same.and_then(|other| self.images.get(other)) {
+            Some(image) => image.clone(),
+            None => decode(&data)?,
+        };
+        self.insert(id, image);
+        self.hashes.entry(hash).or_default().push(id.to_string());
+        self.sources.insert(id.to_string(), (hash, data));
+        self.images.get(id)
+    }
+
+    /// Forgets the file asset `id` was loaded from, once it is replaced or removed.
+    fn forget_source(&mut self, id: &str) {
+        let Some((hash, _)) = self.sources.remove(id) else {
+            return;
+        };
+        if let Some(ids) = self.hashes.get_mut(&hash) {
+            ids.retain(|other| other != id);
+            if ids.is_empty() {
+                self.hashes.remove(&hash);
+            }
+        }
+    }
 }
 
 /// Asset Map Methods
@@ -29,6 +79,7 @@ pub trait AssetsMethods<T> {
 
 impl AssetsMethods<Image> for Assets {
     fn insert(&mut self, k: &str, v: Image) -> Option<Image> {
+        self.forget_source(k);
         self.assets.insert(k.to_string(), Asset::Image);
         self.images.insert(k.to_string(), v)
     }
@@ -39,6 +90,44 @@ impl AssetsMethods<Image> for Assets {
         self.images.get_mut(k)
     }
     fn remove(&mut self, k: &str) -> Option<Image> {
+        self.forget_source(k);
         self.images.remove(k)
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_load_dedups_identical_content() {
+    use std::cell::Cell;
+
+    let dir = std::env::temp_dir().join(format!("naqsh-assets-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (a, b, c) = (dir.join("a.bin"), dir.join("b.bin"), dir.join("c.bin"));
+    std::fs::write(&a, [1, 2, 3, 4]).unwrap();
+    std::fs::write(&b, [1, 2, 3, 4]).unwrap();
+    std::fs::write(&c, [5, 6, 7, 8]).unwrap();
+
+    let decodes = Cell::new(0);
+    let decode = |data: &[u8]| {
+        decodes.set(decodes.get() + 1);
+        Some(Image::from((1, 1, data.to_vec())))
+    };
+
+    let mut assets = Assets::default();
+    assert!(assets.load("first", &a, decode).is_some());
+    assert!(assets.load("second", &b, decode).is_some());
+    assert_eq!(decodes.get(), 1);
+    assert_eq!(assets.get("second").unwrap()[..], [1, 2, 3, 4]);
+
+    assert!(assets.load("third", &c, decode).is_some());
+    assert_eq!(decodes.get(), 2);
+    assert!(assets.load("missing", &dir.join("missing.bin"), decode).is_none());
+
+    // Replaced and removed assets are no longer reused
+    assets.insert("first", Image::from((1, 1, vec![9, 9, 9, 9])));
+    assets.remove("second");
+    assert_eq!(assets.load("fourth", &a, decode).unwrap()[..], [1, 2, 3, 4]);
+    assert_eq!(decodes.get(), 3);
+    assert!(assets.hashes.values().all(|ids| !ids.iter().any(|id| id == "first" || id == "second")));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
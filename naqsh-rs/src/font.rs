@@ -1,5 +1,9 @@
 use swash::{Attributes, CacheKey, Charmap, FontRef};
 
+// Basic Latin and Arabic subset of DejaVu Sans, see testdata/fonts/LICENSE.
+#[cfg(test)]
+pub(crate) const TEST_FONT: &[u8] = include_bytes!("../testdata/fonts/DejaVuSans-subset.ttf");
+
 pub struct Font {
     // Full content of the font file
     data: Vec<u8>,
@@ -13,7 +17,11 @@ impl Font {
     pub fn from_file(path: &str, index: usize) -> Option<Self> {
         // Read the full font file
         let data = std::fs::read(path).ok()?;
-        // Create a temporary font reference for the first font in the file.
+        Self::from_bytes(data, index)
+    }
+
+    pub fn from_bytes(data: Vec<u8>, index: usize) -> Option<Self> {
+        // Create a temporary font reference for the font at `index` in the data.
         // This will do some basic validation, compute the necessary offset
         // and generate a fresh cache key for us.
         let font = FontRef::from_index(&data, index)?;
@@ -1,83 +1,309 @@
 #![allow(unused_variables, dead_code)]
+use std::fmt;
+
 use crate::{image::Image, object::Rect};
 
+use super::{Color, ColorInt, Text};
+
+/// How a [Canvas] combines what is drawn with the pixels already on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// Replace the canvas pixels, alpha included.
+    Src,
+    /// Alpha-blend over the canvas pixels, so semi-transparent pixels let the canvas show
+    /// through.
+    #[default]
+    SrcOver,
+}
+
+#[derive(Clone)]
 pub struct Canvas {
     image: Image,
+    // Color shapes such as Rect are filled with
+    fill: ColorInt,
+    blend_mode: BlendMode,
 }
 
-pub trait Draw<T> {
-    fn draw(self, object: T, origin: (i32, i32)) -> Self;
-}
+impl Canvas {
+    /// Creates a canvas which draws onto `image`.
+    pub fn new(image: Image) -> Self {
+        Self { image, fill: Color::BLACK, blend_mode: BlendMode::default() }
+    }
 
-impl Draw<Image> for Canvas {
-    fn draw(mut self, mut object: Image, origin: (i32, i32)) -> Self {
-        // where to start drawing in canvas
-        let start_x;
-        // how many columns should i skip while drawing image
-        let skip_x;
-        if origin.0 < 0 {
-            start_x = (object.width - origin.0 as usize) * 4;
-            skip_x = 0;
-        } else {
-            start_x = 0;
-            skip_x = (origin.0 as usize) * 4
-        }
+    /// Creates a canvas which draws onto a transparent `width` x `height` image of `format`.
+    pub fn with_size(width: usize, height: usize, format: String) -> Self {
+        Self::new(Image::new(width, height, format))
+    }
 
-        // where to stop drawing
-        let stop_x;
-        if object.width < self.image.width {
-            stop_x = object.width * 4;
-        } else {
-            stop_x = self.image.width * 4;
-        }
+    /// Returns the image drawn onto.
+    pub fn into_image(self) -> Image {
+        self.image
+    }
 
-        let start_y;
-        let skip_y;
-        if origin.1 < 0 {
-            start_y = (object.height - origin.1 as usize) * 4;
-            skip_y = 0;
-        } else {
-            start_y = 0;
-            skip_y = (origin.1 as usize) * 4;
+    /// Returns the canvas with every pixel set to `color`, replacing whatever was drawn.
+    pub fn clear(mut self, color: ColorInt) -> Self {
+        let rgba = [color.red(), color.green(), color.blue(), color.alpha()];
+        for pixel in self.image.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&rgba);
         }
+        self
+    }
+
+    /// Returns the canvas with shapes drawn from now on filled with `color`. Defaults to opaque
+    /// black.
+    ///
+    /// <pre>
+    /// let canvas = canvas.with_fill(Color::RED).draw(Rect::new(0, 0, 10, 10), (0, 0));
+    /// </pre>
+    pub fn with_fill(mut self, color: ColorInt) -> Self {
+        self.fill = color;
+        self
+    }
+
+    /// Returns the canvas with everything drawn from now on combined with it using `mode`.
+    /// Defaults to [BlendMode::SrcOver].
+    pub fn with_blend_mode(mut self, mode: BlendMode) -> Self {
+        self.blend_mode = mode;
+        self
+    }
 
-        let stop_y;
-        if object.height < self.image.height {
-            stop_y = object.height * 4;
-        } else {
-            stop_y = self.image.height * 4;
+    /// Copies `object` onto the canvas with its top-left corner at `origin`. Parts of `object`
+    /// outside of the canvas are clipped.
+    fn copy(&mut self, object: &Image, origin: (i32, i32)) {
+        // Columns and rows of the object which land on the canvas
+        let skip_x = (-origin.0).max(0) as usize;
+        let skip_y = (-origin.1).max(0) as usize;
+        let x = origin.0.max(0) as usize;
+        let y = origin.1.max(0) as usize;
+        let width = object.width.saturating_sub(skip_x).min(self.image.width.saturating_sub(x));
+        let height = object.height.saturating_sub(skip_y).min(self.image.height.saturating_sub(y));
+        if width == 0 {
+            return;
         }
 
-        let width = self.image.width;
-        let mut y = 0;
-        let mut ix;
-        let mut iy = 0;
-        for row in self.image.chunks_mut(width) {
-            if y < start_y {
-                continue;
-            }
-            if y > stop_y {
+        let stride = self.image.stride();
+        for row in 0..height {
+            let Some(src) = object.row_bytes(skip_y + row) else {
                 break;
-            }
+            };
+            let start = (y + row) * stride + x * 4;
+            self.image[start..start + width * 4].copy_from_slice(&src[skip_x * 4..(skip_x + width) * 4]);
+        }
+    }
+}
+
+pub trait Draw<T> {
+    fn draw(self, object: T, origin: (i32, i32)) -> Self;
+}
 
-            ix = 0;
-            let chunk = object.get_row(iy + skip_y).unwrap();
-            for x in start_x..stop_x {
-                row[x] = *chunk.get(ix + skip_x).unwrap();
+/// Errors returned by [TryDraw::try_draw].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DrawError {
+    /// The object placed at `origin` does not fit inside the canvas.
+    OutOfBounds { origin: (i32, i32) },
+    /// Row `y` of the object has no pixel data.
+    MissingRow(usize),
+}
 
-                ix += 1;
+impl fmt::Display for DrawError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrawError::OutOfBounds { origin: (x, y) } => {
+                write!(f, "object drawn at ({x}, {y}) does not fit inside the canvas")
             }
+            DrawError::MissingRow(y) => write!(f, "row {y} of the object has no pixel data"),
+        }
+    }
+}
+
+impl std::error::Error for DrawError {}
 
-            y += 1;
-            iy += 1;
+/// Like [Draw], but reports objects which can not be drawn as errors instead of panicking.
+pub trait TryDraw<T>: Sized {
+    fn try_draw(self, object: T, origin: (i32, i32)) -> Result<Self, DrawError>;
+}
+
+impl TryDraw<Image> for Canvas {
+    /// Copies `object` onto the canvas with its top-left corner at `origin`. The object must
+    /// fit inside the canvas entirely.
+    fn try_draw(mut self, object: Image, origin: (i32, i32)) -> Result<Self, DrawError> {
+        let out_of_bounds = DrawError::OutOfBounds { origin };
+        let x = usize::try_from(origin.0).map_err(|_| out_of_bounds.clone())?;
+        let y = usize::try_from(origin.1).map_err(|_| out_of_bounds.clone())?;
+        if x + object.width > self.image.width || y + object.height > self.image.height {
+            return Err(out_of_bounds);
         }
 
+        let stride = self.image.stride();
+        for row in 0..object.height {
+            let src = object.row_bytes(row).ok_or(DrawError::MissingRow(row))?;
+            let start = (y + row) * stride + x * 4;
+            self.image[start..start + src.len()].copy_from_slice(src);
+        }
+
+        Ok(self)
+    }
+}
+
+impl Draw<Image> for Canvas {
+    /// Draws `object` onto the canvas with its top-left corner at `origin`, which may be
+    /// negative, combining it according to the blend mode of the canvas. Parts of `object`
+    /// outside of the canvas are clipped.
+    fn draw(mut self, object: Image, origin: (i32, i32)) -> Self {
+        match self.blend_mode {
+            BlendMode::Src => self.copy(&object, origin),
+            BlendMode::SrcOver => {
+                let area = Rect::new(0, 0, object.width as i32, object.height as i32);
+                self.image.blend_region(&object, &area, origin);
+            }
+        }
         self
     }
 }
 
 impl Draw<Rect> for Canvas {
-    fn draw(self, object: Rect, origin: (i32, i32)) -> Self {
-        todo!()
+    /// Fills `object`, moved by `origin`, with the fill color of the canvas, see
+    /// [Canvas::with_fill], combined according to the blend mode of the canvas. Parts outside
+    /// of the canvas are clipped.
+    fn draw(mut self, mut object: Rect, origin: (i32, i32)) -> Self {
+        object.offset(origin.0, origin.1);
+        match self.blend_mode {
+            BlendMode::Src if !object.is_empty() => {
+                let fill = Image::solid(object.width() as usize, object.height() as usize, self.fill);
+                self.copy(&fill, (object.left, object.top));
+            }
+            BlendMode::Src => {}
+            BlendMode::SrcOver => self.image.fill_rect(&object, self.fill),
+        }
+        self
+    }
+}
+
+impl Draw<Text<'_>> for Canvas {
+    /// Draws `object` in the fill color of the canvas with the top-left corner of its line at
+    /// `origin`, combined according to the blend mode of the canvas. Parts outside of the
+    /// canvas are clipped.
+    fn draw(self, object: Text<'_>, origin: (i32, i32)) -> Self {
+        let (image, (left, top)) = object.render(self.fill);
+        self.draw(image, (origin.0 + left, origin.1 + top))
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_try_draw_reports_errors() {
+    use crate::graphics::Color;
+
+    let canvas = || Canvas::new(Image::new(4, 4, String::new()));
+
+    let truncated = Image::from((2, 2, vec![255; 8]));
+    assert_eq!(canvas().try_draw(truncated, (0, 0)).err(), Some(DrawError::MissingRow(1)));
+
+    let sprite = || Image::solid(2, 2, Color::RED);
+    assert_eq!(canvas().try_draw(sprite(), (-1, 0)).err(), Some(DrawError::OutOfBounds { origin: (-1, 0) }));
+    assert_eq!(canvas().try_draw(sprite(), (3, 2)).err(), Some(DrawError::OutOfBounds { origin: (3, 2) }));
+
+    let drawn = canvas().try_draw(sprite(), (2, 2)).unwrap();
+    let bottom_right = drawn.image.row_bytes(3).unwrap();
+    assert_eq!(bottom_right[8..16], [255, 0, 0, 255, 255, 0, 0, 255]);
+    assert_eq!(bottom_right[0..8], [0; 8]);
+}
+
+#[test]
+fn test_draw_rect() {
+    let canvas = Canvas::new(Image::solid(4, 4, Color::WHITE))
+        .with_fill(Color::RED)
+        .draw(Rect::new(-2, -2, 1, 1), (0, 0))
+        .with_fill(Color::argb(128, 0, 0, 0))
+        .draw(Rect::new(0, 0, 10, 1), (2, 3));
+
+    let pixel = |x: usize, y: usize| canvas.image.row_bytes(y).unwrap()[x * 4..x * 4 + 4].to_vec();
+    assert_eq!(pixel(0, 0), [255, 0, 0, 255]);
+    assert_eq!(pixel(1, 0), [255, 255, 255, 255]);
+    assert_eq!(pixel(1, 3), [255, 255, 255, 255]);
+    assert_eq!(pixel(2, 3), [127, 127, 127, 255]);
+    assert_eq!(pixel(3, 3), [127, 127, 127, 255]);
+}
+
+#[test]
+fn test_draw_image_offsets() {
+    // A 2x2 sprite with distinct pixels 1, 2, 3, 4 drawn onto a 3x3 canvas
+    let sprite = || Image::from((2, 2, (1..=4).flat_map(|i| [i, i, i, 255]).collect::<Vec<u8>>()));
+    let drawn = |origin: (i32, i32)| -> Vec<u8> {
+        let canvas = Canvas::new(Image::new(3, 3, String::new())).draw(sprite(), origin);
+        canvas.image.chunks_exact(4).map(|pixel| pixel[0]).collect()
+    };
+
+    assert_eq!(drawn((0, 0)), vec![1, 2, 0, 3, 4, 0, 0, 0, 0]);
+    assert_eq!(drawn((1, 1)), vec![0, 0, 0, 0, 1, 2, 0, 3, 4]);
+    assert_eq!(drawn((2, 0)), vec![0, 0, 1, 0, 0, 3, 0, 0, 0]);
+    assert_eq!(drawn((-1, 0)), vec![2, 0, 0, 4, 0, 0, 0, 0, 0]);
+    assert_eq!(drawn((-1, -1)), vec![4, 0, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(drawn((1, 2)), vec![0, 0, 0, 0, 0, 0, 0, 1, 2]);
+    assert_eq!(drawn((-2, 0)), vec![0; 9]);
+    assert_eq!(drawn((3, 3)), vec![0; 9]);
+}
+
+#[test]
+fn test_canvas_with_size_and_clear() {
+    let canvas = Canvas::with_size(3, 2, String::from("png"));
+    let image = canvas.clone().into_image();
+    assert_eq!((image.width, image.height), (3, 2));
+    assert!(image.iter().all(|&byte| byte == 0));
+
+    let image = canvas.clear(Color::argb(128, 10, 20, 30)).into_image();
+    assert_eq!(image.chunks_exact(4).collect::<Vec<_>>(), vec![[10, 20, 30, 128]; 6]);
+}
+
+#[test]
+fn test_blend_modes() {
+    let background = || Canvas::new(Image::solid(1, 1, Color::WHITE));
+    let half_red = || Image::solid(1, 1, Color::argb(128, 255, 0, 0));
+
+    let over = background().draw(half_red(), (0, 0)).into_image();
+    assert_eq!(over[..], [255, 127, 127, 255]);
+    let src = background().with_blend_mode(BlendMode::Src).draw(half_red(), (0, 0)).into_image();
+    assert_eq!(src[..], [255, 0, 0, 128]);
+
+    let rect = background()
+        .with_blend_mode(BlendMode::Src)
+        .with_fill(Color::TRANSPARENT)
+        .draw(Rect::new(0, 0, 1, 1), (0, 0))
+        .into_image();
+    assert_eq!(rect[..], [0, 0, 0, 0]);
+}
+
+#[test]
+fn test_draw_truncated_image() {
+    // The buffer only holds the first of the two rows
+    let truncated = || Image::from((2, 2, vec![255; 8]));
+
+    for mode in [BlendMode::SrcOver, BlendMode::Src] {
+        let image = Canvas::with_size(4, 4, String::new())
+            .with_blend_mode(mode)
+            .draw(truncated(), (0, 0))
+            .into_image();
+        assert_eq!(image.row_bytes(0).unwrap()[..8], [255; 8]);
+        assert!(image.row_bytes(0).unwrap()[8..].iter().all(|&byte| byte == 0));
+        assert!(image.row_bytes(1).unwrap().iter().all(|&byte| byte == 0));
+    }
+}
+
+#[test]
+fn test_draw_text() {
+    use crate::font::{Font, TEST_FONT};
+
+    let font = Font::from_bytes(TEST_FONT.to_vec(), 0).unwrap();
+    let canvas = Canvas::new(Image::solid(80, 30, Color::WHITE)).with_fill(Color::RED);
+    let image = canvas.draw(Text::new("naqsh", &font, 20.0), (5, 5)).into_image();
+
+    let inked: Vec<(usize, usize)> = (0..image.height)
+        .flat_map(|y| (0..image.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| image.row_bytes(y).unwrap()[x * 4 + 1] < 128)
+        .collect();
+    assert!(!inked.is_empty());
+    // Glyphs are filled with the fill color and stay inside the line box below the origin
+    assert!(inked.iter().all(|&(x, y)| (5..65).contains(&x) && (5..29).contains(&y)));
+    let (x, y) = inked[0];
+    assert_eq!(image.row_bytes(y).unwrap()[x * 4], 255);
+    assert_eq!(Text::new("", &font, 20.0).render(Color::RED).0.iter().filter(|&&b| b != 0).count(), 0);
+}
@@ -1,4 +1,6 @@
-use super::ColorInt;
+use std::fmt;
+
+use super::{ColorInt, ColorUtils};
 
 ///<p>The <code>Color</code> struct provides methods for creating, converting and manipulating colors.
 /// Colors have three different representations:</p>
@@ -269,25 +271,25 @@ impl Color {
 
     /// Return the alpha component of a [ColorInt]. This is the same as saying
     /// `((color >> 24) & 0xff) as u8`
-    pub fn alpha(color: ColorInt) -> u8 {
+    pub const fn alpha(color: ColorInt) -> u8 {
         color.alpha()
     }
 
     /// Return the red component of a [ColorInt]. This is the same as saying
     /// `((color >> 16) & 0xFF) as u8`
-    pub fn red(color: ColorInt) -> u8 {
+    pub const fn red(color: ColorInt) -> u8 {
         color.red()
     }
 
     /// Return the green component of a [ColorInt]. This is the same as saying
     /// `((color >> 8) & 0xFF) as u8`
-    pub fn green(color: ColorInt) -> u8 {
+    pub const fn green(color: ColorInt) -> u8 {
         color.green()
     }
 
     /// Return the blue component of a [ColorInt]. This is the same as saying
     /// `(color & 0xFF) as u8`
-    pub fn blue(color: ColorInt) -> u8 {
+    pub const fn blue(color: ColorInt) -> u8 {
         color.blue()
     }
 
@@ -296,11 +298,8 @@ impl Color {
     /// `red` is the red component  of the color.
     /// `green` is the green component  of the color.
     /// `blue` is the blue component  of the color.
-    pub fn rgb(red: u8, green: u8, blue: u8) -> ColorInt {
-        ColorInt(0xff000000u32 as i32)
-            | (ColorInt(red as i32) << 16)
-            | (ColorInt(green as i32) << 8)
-            | ColorInt(blue as i32)
+    pub const fn rgb(red: u8, green: u8, blue: u8) -> ColorInt {
+        Self::argb(0xff, red, green, blue)
     }
 
     /// Returns a [ColorInt] from alpha, red, green, blue components.
@@ -308,14 +307,187 @@ impl Color {
     /// `red` is the red component  of the color.
     /// `green` is the green component  of the color.
     /// `blue` is the blue component  of the color.
-    pub fn argb(alpha: u8, red: u8, green: u8, blue: u8) -> ColorInt {
-        (ColorInt(alpha as i32) << 24)
-            | (ColorInt(red as i32) << 16)
-            | (ColorInt(green as i32) << 8)
-            | ColorInt(blue as i32)
+    pub const fn argb(alpha: u8, red: u8, green: u8, blue: u8) -> ColorInt {
+        ColorInt((alpha as i32) << 24 | (red as i32) << 16 | (green as i32) << 8 | blue as i32)
+    }
+
+    /// Parses a color string into a [ColorInt]. Supported formats are:
+    /// <ul>
+    ///     <li>`#RRGGBB`</li>
+    ///     <li>`#AARRGGBB`</li>
+    ///     <li>`#RGB`, where each digit is doubled</li>
+    ///     <li>`rgb(R, G, B)`</li>
+    ///     <li>`rgba(R, G, B, A)`</li>
+    ///     <li>`hsl(H, S%, L%)`</li>
+    ///     <li>`hsla(H, S%, L%, A)`</li>
+    /// </ul>
+    /// Hex digits may be upper or lowercase and surrounding whitespace is ignored. Colors
+    /// without alpha are fully opaque.
+    ///
+    /// The channels of `rgb()` and `rgba()` are numbers in `[0, 255]` or percentages such as
+    /// `50%`, clamped to that range. The alpha of `rgba()` is a number in `[0.0, 1.0]` or a
+    /// percentage.
+    ///
+    /// The hue of `hsl()` and `hsla()` is in degrees and wraps around `[0, 360)`. Saturation and
+    /// lightness are percentages, constrained to `[0%, 100%]`. Alpha is as for `rgba()`.
+    pub fn parse_color(color_string: &str) -> Result<ColorInt, ParseColorError> {
+        let trimmed = color_string.trim();
+        if trimmed.is_empty() {
+            return Err(ParseColorError::Empty);
+        }
+        let invalid = || ParseColorError::InvalidFormat(color_string.to_string());
+
+        if let Some(hex) = trimmed.strip_prefix('#') {
+            return Self::parse_hex(hex).ok_or_else(invalid);
+        }
+        let (name, args) = Self::split_function(trimmed).ok_or_else(invalid)?;
+        let color = match (name.to_ascii_lowercase().as_str(), args.as_slice()) {
+            ("rgb", &[r, g, b]) => Self::parse_rgba(r, g, b, None),
+            ("rgba", &[r, g, b, a]) => Self::parse_rgba(r, g, b, Some(a)),
+            ("hsl", &[h, s, l]) => Self::parse_hsla(h, s, l, None),
+            ("hsla", &[h, s, l, a]) => Self::parse_hsla(h, s, l, Some(a)),
+            _ => None,
+        };
+        color.ok_or_else(invalid)
+    }
+
+    /// Parses the digits of a `#RGB`, `#RRGGBB` or `#AARRGGBB` color.
+    fn parse_hex(hex: &str) -> Option<ColorInt> {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        match hex.len() {
+            3 => {
+                let expand = |digit: u32| (digit * 0x11) as u8;
+                Some(Self::rgb(expand(value >> 8), expand((value >> 4) & 0xf), expand(value & 0xf)))
+            }
+            6 => Some(ColorInt((0xff00_0000 | value) as i32)),
+            8 => Some(ColorInt(value as i32)),
+            _ => None,
+        }
+    }
+
+    /// Splits `name(a, b, c)` into its name and trimmed arguments.
+    fn split_function(s: &str) -> Option<(&str, Vec<&str>)> {
+        let (name, rest) = s.split_once('(')?;
+        let args = rest.strip_suffix(')')?;
+        Some((name.trim(), args.split(',').map(str::trim).collect()))
+    }
+
+    /// Parses a number, or a percentage of `max`, clamped to `[0, max]`.
+    fn parse_component(s: &str, max: f32) -> Option<f32> {
+        let value = match s.strip_suffix('%') {
+            Some(percent) => percent.trim_end().parse::<f32>().ok()? / 100f32 * max,
+            None => s.parse::<f32>().ok()?,
+        };
+        value.is_finite().then(|| value.clamp(0f32, max))
+    }
+
+    fn parse_rgba(r: &str, g: &str, b: &str, a: Option<&str>) -> Option<ColorInt> {
+        let channel = |s| Self::parse_component(s, 255f32).map(|value| value.round() as u8);
+        let alpha = match a {
+            Some(a) => (Self::parse_component(a, 1f32)? * 255f32).round() as u8,
+            None => 0xff,
+        };
+        Some(Self::argb(alpha, channel(r)?, channel(g)?, channel(b)?))
+    }
+
+    fn parse_hsla(h: &str, s: &str, l: &str, a: Option<&str>) -> Option<ColorInt> {
+        let hue = h.strip_suffix("deg").unwrap_or(h).trim_end().parse::<f32>().ok()?;
+        if !hue.is_finite() {
+            return None;
+        }
+        let percent = |s: &str| {
+            let value = s.strip_suffix('%')?.trim_end().parse::<f32>().ok()?;
+            value.is_finite().then(|| ColorUtils::constrain(value / 100f32, 0f32, 1f32))
+        };
+        let rgb = ColorUtils::hsl_to_rgb([hue.rem_euclid(360f32), percent(s)?, percent(l)?]);
+        match a {
+            Some(a) => {
+                let alpha = (Self::parse_component(a, 1f32)? * 255f32).round() as u8;
+                Some(ColorUtils::set_alpha_component(rgb, alpha))
+            }
+            None => Some(rgb),
+        }
+    }
+}
+
+/// Error returned by [Color::parse_color].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseColorError {
+    /// The color string is empty or only whitespace.
+    Empty,
+    /// The color string is not in a supported format.
+    InvalidFormat(String),
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty color string"),
+            Self::InvalidFormat(color) => {
+                write!(f, "invalid color `{color}`, expected `#RGB`, `#RRGGBB`, `#AARRGGBB`, `rgb()`, `rgba()`, `hsl()` or `hsla()`")
+            }
+        }
     }
+}
+
+impl std::error::Error for ParseColorError {}
+#[test]
+fn test_const_constructors() {
+    const MY_COLOR: ColorInt = Color::rgb(10, 20, 30);
+    const FADED: ColorInt = Color::argb(0x80, 10, 20, 30);
+    const RED: u8 = Color::red(MY_COLOR);
+
+    assert_eq!(MY_COLOR, ColorInt(0xFF0A141Eu32 as i32));
+    assert_eq!(FADED, ColorInt(0x800A141Eu32 as i32));
+    assert_eq!(RED, 10);
+    assert_eq!(FADED.alpha(), 0x80);
+}
+
+#[test]
+fn test_parse_color_hex() {
+    assert_eq!(Color::parse_color("#FF8000"), Ok(Color::rgb(0xff, 0x80, 0x00)));
+    assert_eq!(Color::parse_color("  #80ff8000\n"), Ok(Color::argb(0x80, 0xff, 0x80, 0x00)));
+    assert_eq!(Color::parse_color("#f80"), Ok(Color::rgb(0xff, 0x88, 0x00)));
+    assert_eq!(Color::parse_color("#aBcDeF"), Ok(Color::rgb(0xab, 0xcd, 0xef)));
+
+    assert_eq!(Color::parse_color(" "), Err(ParseColorError::Empty));
+    for invalid in ["FF8000", "#FF80", "#GG8000", "#+f80", "#FF8000FF00"] {
+        assert_eq!(Color::parse_color(invalid), Err(ParseColorError::InvalidFormat(invalid.to_string())));
+    }
+}
+
+#[test]
+fn test_parse_color_rgb_functions() {
+    assert_eq!(Color::parse_color("rgb(255, 128, 0)"), Ok(Color::rgb(255, 128, 0)));
+    assert_eq!(Color::parse_color(" RGB( 255 ,128,0 ) "), Ok(Color::rgb(255, 128, 0)));
+    assert_eq!(Color::parse_color("rgb(300, -20, 12.4)"), Ok(Color::rgb(255, 0, 12)));
+    assert_eq!(Color::parse_color("rgba(255, 128, 0, 0.5)"), Ok(Color::argb(128, 255, 128, 0)));
+    assert_eq!(Color::parse_color("rgba(0, 0, 0, 2)"), Ok(Color::argb(255, 0, 0, 0)));
+    assert_eq!(Color::parse_color("rgb(100%, 50%, 0%)"), Ok(Color::rgb(255, 128, 0)));
+    assert_eq!(Color::parse_color("rgba(0, 0, 0, 25%)"), Ok(Color::argb(64, 0, 0, 0)));
+
+    for invalid in ["rgb(1, 2)", "rgba(1, 2, 3)", "rgb(1, 2, x)", "rgb(1, 2, 3", "cmyk(1, 2, 3)", "rgb(1, 2, NaN)"] {
+        assert_eq!(Color::parse_color(invalid), Err(ParseColorError::InvalidFormat(invalid.to_string())));
+    }
+}
+
+#[test]
+fn test_parse_color_hsl_functions() {
+    assert_eq!(Color::parse_color("hsl(0, 100%, 50%)"), Ok(Color::rgb(255, 0, 0)));
+    assert_eq!(Color::parse_color("hsl(120, 100%, 25%)"), Ok(Color::rgb(0, 128, 0)));
+    assert_eq!(Color::parse_color("hsl(210, 50%, 40%)"), Ok(ColorUtils::hsl_to_rgb([210.0, 0.5, 0.4])));
+    assert_eq!(Color::parse_color(" HSL( 480deg , 100% ,50% ) "), Color::parse_color("hsl(120, 100%, 50%)"));
+    assert_eq!(Color::parse_color("hsl(-120, 100%, 50%)"), Color::parse_color("hsl(240, 100%, 50%)"));
+    assert_eq!(Color::parse_color("hsl(0, 150%, -10%)"), Ok(Color::rgb(0, 0, 0)));
+    assert_eq!(
+        Color::parse_color("hsla(210, 50%, 40%, 0.8)"),
+        Ok(ColorUtils::set_alpha_component(ColorUtils::hsl_to_rgb([210.0, 0.5, 0.4]), 204))
+    );
 
-    pub fn parse_color(color_string: String) -> ColorInt {
-        todo!()
+    for invalid in ["hsl(0, 100, 50%)", "hsla(0, 100%, 50%)", "hsl(red, 100%, 50%)"] {
+        assert_eq!(Color::parse_color(invalid), Err(ParseColorError::InvalidFormat(invalid.to_string())));
     }
-}
\ No newline at end of file
+}
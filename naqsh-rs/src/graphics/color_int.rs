@@ -12,10 +12,15 @@ use std::num::Wrapping;
 
 use serde::Deserialize;
 
+use super::{Color, ParseColorError};
+
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default, Hash, Deserialize)]
-#[serde(transparent)]
+#[serde(try_from = "RawColor")]
 #[repr(transparent)]
 /// Packed color integer.
+///
+/// Deserializes from an integer, either signed or as the unsigned `0xAARRGGBB` value, or from
+/// any string accepted by [Color::parse_color], such as `"#ff3366"`.
 pub struct ColorInt(pub i32);
 
 impl fmt::Debug for ColorInt {
@@ -54,6 +59,16 @@ impl fmt::UpperHex for ColorInt {
     }
 }
 
+/// Displays a [ColorInt] as 8 lowercase hex digits in ARGB order. See [ColorInt::hex].
+#[derive(Debug, Clone, Copy)]
+pub struct HexColorInt(ColorInt);
+
+impl fmt::Display for HexColorInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08x}", self.0 .0 as u32)
+    }
+}
+
 impl Add for ColorInt {
     type Output = Self;
 
@@ -252,12 +267,21 @@ impl BitAndAssign<i32> for ColorInt {
     }
 }
 
+/// Shifts follow Java's semantics, which the ported code relies on: only the lowest 5 bits
+/// of the shift amount are used, so shifting by 33 is the same as shifting by 1.
 impl Shl<usize> for ColorInt {
     type Output = Self;
 
     fn shl(self, rhs: usize) -> Self::Output {
-        let num = Wrapping(self.0) << rhs;
-        Self(num.0)
+        Self(self.0 << (rhs & 31))
+    }
+}
+
+impl Shl<i64> for ColorInt {
+    type Output = Self;
+
+    fn shl(self, rhs: i64) -> Self::Output {
+        Self(self.0 << (rhs & 31))
     }
 }
 
@@ -267,12 +291,27 @@ impl ShlAssign<usize> for ColorInt {
     }
 }
 
+impl ShlAssign<i64> for ColorInt {
+    fn shl_assign(&mut self, rhs: i64) {
+        *self = *self << rhs;
+    }
+}
+
+/// Arithmetic (sign-extending) shift, using only the lowest 5 bits of the shift amount like
+/// [Shl].
 impl Shr<usize> for ColorInt {
     type Output = Self;
 
     fn shr(self, rhs: usize) -> Self::Output {
-        let num = Wrapping(self.0) >> rhs;
-        Self(num.0)
+        Self(self.0 >> (rhs & 31))
+    }
+}
+
+impl Shr<i64> for ColorInt {
+    type Output = Self;
+
+    fn shr(self, rhs: i64) -> Self::Output {
+        Self(self.0 >> (rhs & 31))
     }
 }
 
@@ -282,12 +321,40 @@ impl ShrAssign<usize> for ColorInt {
     }
 }
 
+impl ShrAssign<i64> for ColorInt {
+    fn shr_assign(&mut self, rhs: i64) {
+        *self = *self >> rhs;
+    }
+}
+
 impl From<i32> for ColorInt {
     fn from(value: i32) -> Self {
         ColorInt(value)
     }
 }
 
+#[derive(Deserialize)]
+#[serde(untagged)]
+/// Forms a [ColorInt] can be written in.
+enum RawColor {
+    Int(i64),
+    Text(String),
+}
+
+impl TryFrom<RawColor> for ColorInt {
+    type Error = ParseColorError;
+
+    fn try_from(raw: RawColor) -> Result<Self, Self::Error> {
+        match raw {
+            RawColor::Int(int) if (i32::MIN as i64..=u32::MAX as i64).contains(&int) => {
+                Ok(ColorInt(int as u32 as i32))
+            }
+            RawColor::Int(int) => Err(ParseColorError::InvalidFormat(int.to_string())),
+            RawColor::Text(text) => Color::parse_color(&text),
+        }
+    }
+}
+
 impl Deref for ColorInt {
     type Target = i32;
 
@@ -303,19 +370,96 @@ impl DerefMut for ColorInt {
 }
 
 impl ColorInt {
-    pub fn alpha(&self) -> u8 {
-        ((*self >> 24) & 0xff).0 as u8
+    /// Returns a wrapper that displays this color as unsigned ARGB hex with leading zeros,
+    /// e.g. `ff0000ff` for opaque blue.
+    pub fn hex(&self) -> HexColorInt {
+        HexColorInt(*self)
+    }
+
+    /// Returns the color as bytes in big-endian order, i.e. `[a, r, g, b]`.
+    pub const fn to_be_bytes(&self) -> [u8;4] {
+        self.0.to_be_bytes()
+    }
+
+    /// Returns the color as bytes in little-endian order, i.e. `[b, g, r, a]`.
+    pub const fn to_le_bytes(&self) -> [u8;4] {
+        self.0.to_le_bytes()
+    }
+
+    /// Creates a color from bytes in big-endian order, i.e. `[a, r, g, b]`.
+    pub const fn from_be_bytes(bytes: [u8;4]) -> ColorInt {
+        ColorInt(i32::from_be_bytes(bytes))
+    }
+
+    /// Creates a color from bytes in little-endian order, i.e. `[b, g, r, a]`.
+    pub const fn from_le_bytes(bytes: [u8;4]) -> ColorInt {
+        ColorInt(i32::from_le_bytes(bytes))
+    }
+
+    pub const fn alpha(&self) -> u8 {
+        (self.0 >> 24) as u8
     }
 
-    pub fn red(&self) -> u8 {
-        ((*self >> 16) & 0xFF).0 as u8
+    pub const fn red(&self) -> u8 {
+        (self.0 >> 16) as u8
     }
 
-    pub fn green(&self) -> u8 {
-        ((*self >> 8) & 0xFF).0 as u8
+    pub const fn green(&self) -> u8 {
+        (self.0 >> 8) as u8
     }
 
-    pub fn blue(&self) -> u8 {
-        (*self & 0xFF).0 as u8
+    pub const fn blue(&self) -> u8 {
+        self.0 as u8
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_hex_display() {
+    assert_eq!(ColorInt(0xFF0000FFu32 as i32).hex().to_string(), "ff0000ff");
+    assert_eq!(ColorInt(0x000000FF).hex().to_string(), "000000ff");
+    assert_eq!(format!("#{}", ColorInt(0x80FF3366u32 as i32).hex()), "#80ff3366");
+}
+
+#[test]
+fn test_shift_amount_masked() {
+    let color = ColorInt(0x40302010);
+    assert_eq!(color << 33usize, color << 1usize);
+    assert_eq!(color >> 33usize, color >> 1usize);
+    assert_eq!(color << 33i64, color << 1usize);
+    assert_eq!(color >> -1i64, color >> 31usize);
+    assert_eq!(ColorInt(-256) >> 4usize, ColorInt(-16));
+
+    let mut shifted = color;
+    shifted <<= 40usize;
+    assert_eq!(shifted, color << 8usize);
+}
+
+#[test]
+fn test_byte_order_round_trip() {
+    let color = ColorInt(0x80FF3366u32 as i32);
+    assert_eq!(color.to_be_bytes(), [0x80, 0xFF, 0x33, 0x66]);
+    assert_eq!(color.to_le_bytes(), [0x66, 0x33, 0xFF, 0x80]);
+    assert_eq!(ColorInt::from_be_bytes(color.to_be_bytes()), color);
+    assert_eq!(ColorInt::from_le_bytes(color.to_le_bytes()), color);
+}
+
+#[test]
+fn test_deserialize_int_or_string() {
+    #[derive(Deserialize)]
+    struct Colors {
+        colors: Vec<ColorInt>,
+    }
+
+    let parsed: Colors = toml::from_str(r##"
+        colors = [-16777216, 0xff3366ff, "#ff3366", "rgb(0, 0, 255)"]
+    "##).unwrap();
+    assert_eq!(parsed.colors, vec![
+        ColorInt(0xff000000u32 as i32),
+        ColorInt(0xff3366ffu32 as i32),
+        ColorInt(0xffff3366u32 as i32),
+        ColorInt(0xff0000ffu32 as i32),
+    ]);
+
+    assert!(toml::from_str::<Colors>("colors = [\"#ff33\"]").is_err());
+    assert!(toml::from_str::<Colors>("colors = [0x1ffffffff]").is_err());
+}
@@ -9,6 +9,12 @@ use super::ColorInt;
 pub struct ColorUtils();
 
 impl ColorUtils {
+    const XYZ_WHITE_REFERENCE_X: f64 = 95.047;
+    const XYZ_WHITE_REFERENCE_Y: f64 = 100.0;
+    const XYZ_WHITE_REFERENCE_Z: f64 = 108.883;
+    const XYZ_EPSILON: f64 = 0.008856;
+    const XYZ_KAPPA: f64 = 903.3;
+
     const MIN_ALPHA_SEARCH_MAX_ITERATIONS: i32 = 10;
     const MIN_ALPHA_SEARCH_PRECISION: i32 = 1;
 
@@ -32,7 +38,7 @@ impl ColorUtils {
         let bf = (b as f32) / 255f32;
 
         let max = f32::max(rf, f32::max(gf, bf));
-        let min = f32::min(rf, f32::max(gf, bf));
+        let min = f32::min(rf, f32::min(gf, bf));
         let delta_max_min = max - min;
 
         let mut h: f32;
@@ -79,6 +85,47 @@ impl ColorUtils {
         Self::rgb_to_hsl(Color::red(color), Color::green(color), Color::blue(color), out_hsl)
     }
 
+    /// Convert HSL (hue-saturation-lightness) components to an opaque ARGB color.
+    /// <ul>
+    /// <li>hsl[0] is Hue [0, 360)</li>
+    /// <li>hsl[1] is Saturation [0, 1]</li>
+    /// <li>hsl[2] is Lightness [0, 1]</li>
+    /// </ul>
+    /// Hues outside of [0, 360) wrap around. If saturation or lightness are invalid, the result
+    /// is undefined.
+    pub fn hsl_to_rgb(hsl: [f32;3]) -> ColorInt {
+        let h = hsl[0].rem_euclid(360f32);
+        let s = hsl[1];
+        let l = hsl[2];
+
+        let c = (1f32 - f32::abs(2f32 * l - 1f32)) * s;
+        let m = l - 0.5f32 * c;
+        let x = c * (1f32 - f32::abs((h / 60f32 % 2f32) - 1f32));
+
+        let hue_segment = h as i32 / 60;
+
+        let (r, g, b) = match hue_segment {
+            0 => (c + m, x + m, m),
+            1 => (x + m, c + m, m),
+            2 => (m, c + m, x + m),
+            3 => (m, x + m, c + m),
+            4 => (x + m, m, c + m),
+            5 | 6 => (c + m, m, x + m),
+            _ => (0f32, 0f32, 0f32)
+        };
+
+        Color::rgb(
+            Self::constrain(f32::round(255f32 * r), 0f32, 255f32) as u8,
+            Self::constrain(f32::round(255f32 * g), 0f32, 255f32) as u8,
+            Self::constrain(f32::round(255f32 * b), 0f32, 255f32) as u8
+        )
+    }
+
+    /// Convert HSL components to an opaque ARGB color, see [hsl_to_rgb][ColorUtils::hsl_to_rgb].
+    pub fn hsl_to_color(hue: f32, saturation: f32, lightness: f32) -> ColorInt {
+        Self::hsl_to_rgb([hue, saturation, lightness])
+    }
+
     /// Convert RGB components to its CIE XYZ representative components.
     ///
     /// <p>The resulting XYZ representation will use the D65 illuminant and the CIE
@@ -140,6 +187,240 @@ impl ColorUtils {
         Self::rgb_to_xyz(Color::red(color), Color::green(color), Color::blue(color), out_xyz)
     }
 
+    /// Converts a color from CIE XYZ to its CIE Lab representation.
+    ///
+    /// <p>This method expects the XYZ representation to use the D65 illuminant and the CIE
+    /// 2° Standard Observer (1931).</p>
+    ///
+    /// <ul>
+    /// <li>out_lab[0] is L [0, 100]</li>
+    /// <li>out_lab[1] is a [-128, 127)</li>
+    /// <li>out_lab[2] is b [-128, 127)</li>
+    /// </ul>
+    pub fn xyz_to_lab(x: f64, y: f64, z: f64, out_lab: &mut [f64;3]) {
+        let x = Self::pivot_xyz_component(x / Self::XYZ_WHITE_REFERENCE_X);
+        let y = Self::pivot_xyz_component(y / Self::XYZ_WHITE_REFERENCE_Y);
+        let z = Self::pivot_xyz_component(z / Self::XYZ_WHITE_REFERENCE_Z);
+        out_lab[0] = f64::max(0f64, 116f64 * y - 16f64);
+        out_lab[1] = 500f64 * (x - y);
+        out_lab[2] = 200f64 * (y - z);
+    }
+
+    fn pivot_xyz_component(component: f64) -> f64 {
+        if component > Self::XYZ_EPSILON {
+            f64::powf(component, 1f64 / 3f64)
+        } else {
+            (Self::XYZ_KAPPA * component + 16f64) / 116f64
+        }
+    }
+
+    /// Convert RGB components to its CIE Lab representative components.
+    ///
+    /// <ul>
+    /// <li>out_lab[0] is L [0, 100]</li>
+    /// <li>out_lab[1] is a [-128, 127)</li>
+    /// <li>out_lab[2] is b [-128, 127)</li>
+    /// </ul>
+    pub fn rgb_to_lab(r: u8, g: u8, b: u8, out_lab: &mut [f64;3]) {
+        // First we convert RGB to XYZ
+        Self::rgb_to_xyz(r, g, b, out_lab);
+        // out_lab now contains XYZ
+        Self::xyz_to_lab(out_lab[0], out_lab[1], out_lab[2], out_lab);
+        // out_lab now contains LAB representation
+    }
+
+    /// Converts a color from CIE Lab to its CIE XYZ representation.
+    ///
+    /// <p>The resulting XYZ representation will use the D65 illuminant and the CIE
+    /// 2° Standard Observer (1931).</p>
+    ///
+    /// <ul>
+    /// <li>out_xyz[0] is X [0, 95.047)</li>
+    /// <li>out_xyz[1] is Y [0, 100)</li>
+    /// <li>out_xyz[2] is Z [0, 108.883)</li>
+    /// </ul>
+    pub fn lab_to_xyz(l: f64, a: f64, b: f64, out_xyz: &mut [f64;3]) {
+        let fy = (l + 16f64) / 116f64;
+        let fx = a / 500f64 + fy;
+        let fz = fy - b / 200f64;
+
+        let mut tmp = f64::powf(fx, 3f64);
+        let xr = if tmp > Self::XYZ_EPSILON { tmp } else { (116f64 * fx - 16f64) / Self::XYZ_KAPPA };
+        let yr = if l > Self::XYZ_KAPPA * Self::XYZ_EPSILON { f64::powf(fy, 3f64) } else { l / Self::XYZ_KAPPA };
+        tmp = f64::powf(fz, 3f64);
+        let zr = if tmp > Self::XYZ_EPSILON { tmp } else { (116f64 * fz - 16f64) / Self::XYZ_KAPPA };
+
+        out_xyz[0] = xr * Self::XYZ_WHITE_REFERENCE_X;
+        out_xyz[1] = yr * Self::XYZ_WHITE_REFERENCE_Y;
+        out_xyz[2] = zr * Self::XYZ_WHITE_REFERENCE_Z;
+    }
+
+    /// Converts a color from CIE XYZ to its opaque RGB representation.
+    ///
+    /// <p>This method expects the XYZ representation to use the D65 illuminant and the CIE
+    /// 2° Standard Observer (1931).</p>
+    ///
+    /// Colors outside of the sRGB gamut are clamped.
+    pub fn xyz_to_color(x: f64, y: f64, z: f64) -> ColorInt {
+        let r = (x * 3.2406 + y * -1.5372 + z * -0.4986) / 100f64;
+        let g = (x * -0.9689 + y * 1.8758 + z * 0.0415) / 100f64;
+        let b = (x * 0.0557 + y * -0.2040 + z * 1.0570) / 100f64;
+
+        let to_srgb = |c: f64| {
+            let c = if c > 0.0031308 { 1.055 * f64::powf(c, 1f64 / 2.4) - 0.055 } else { 12.92 * c };
+            f64::round(c * 255f64).clamp(0f64, 255f64) as u8
+        };
+        Color::rgb(to_srgb(r), to_srgb(g), to_srgb(b))
+    }
+
+    /// Converts a color from CIE Lab to its opaque RGB representation, the inverse of
+    /// [rgb_to_lab][ColorUtils::rgb_to_lab]. Colors outside of the sRGB gamut are clamped.
+    /// <ul>
+    /// <li>lab[0] is L [0, 100]</li>
+    /// <li>lab[1] is a [-128, 127)</li>
+    /// <li>lab[2] is b [-128, 127)</li>
+    /// </ul>
+    pub fn lab_to_rgb(lab: [f64;3]) -> ColorInt {
+        let mut xyz = [0f64;3];
+        Self::lab_to_xyz(lab[0], lab[1], lab[2], &mut xyz);
+        Self::xyz_to_color(xyz[0], xyz[1], xyz[2])
+    }
+
+    /// Convert the ARGB color to its CIE Lab representative components.
+    /// The alpha component is ignored.
+    pub fn color_to_lab(color: ColorInt, out_lab: &mut [f64;3]) {
+        Self::rgb_to_lab(Color::red(color), Color::green(color), Color::blue(color), out_lab)
+    }
+
+    /// Converts every color in `colors` to HSL. See [color_to_hsl][ColorUtils::color_to_hsl].
+    pub fn colors_to_hsl(colors: &[ColorInt]) -> Vec<[f32;3]> {
+        colors.iter().map(|&color| {
+            let mut hsl = [0f32;3];
+            Self::color_to_hsl(color, &mut hsl);
+            hsl
+        }).collect()
+    }
+
+    /// Converts every HSL triple in `hsl` to an opaque color. See
+    /// [hsl_to_rgb][ColorUtils::hsl_to_rgb].
+    pub fn hsl_to_colors(hsl: &[[f32;3]]) -> Vec<ColorInt> {
+        hsl.iter().map(|&hsl| Self::hsl_to_rgb(hsl)).collect()
+    }
+
+    /// Returns `count` opaque colors with the given `saturation` and `lightness` and evenly
+    /// spaced hues, starting at red. Useful for categorical palettes.
+    pub fn hue_wheel(count: usize, saturation: f32, lightness: f32) -> Vec<ColorInt> {
+        (0..count)
+            .map(|i| Self::hsl_to_rgb([i as f32 * 360f32 / count as f32, saturation, lightness]))
+            .collect()
+    }
+
+    /// Converts every color in `colors` to CIE Lab. See [color_to_lab][ColorUtils::color_to_lab].
+    pub fn colors_to_lab(colors: &[ColorInt]) -> Vec<[f64;3]> {
+        colors.iter().map(|&color| {
+            let mut lab = [0f64;3];
+            Self::color_to_lab(color, &mut lab);
+            lab
+        }).collect()
+    }
+
+    /// Returns the CIE76 color difference between two colors: the Euclidean distance between
+    /// their CIE Lab representations. The alpha components are ignored.
+    ///
+    /// A difference of about 2.3 is the "just noticeable difference"; colors closer than that
+    /// look the same to most people. CIE76 overstates differences between saturated colors, see
+    /// [distance_ciede2000][ColorUtils::distance_ciede2000] for a more uniform metric.
+    pub fn distance_ciede(a: ColorInt, b: ColorInt) -> f64 {
+        let (mut lab_a, mut lab_b) = ([0f64;3], [0f64;3]);
+        Self::color_to_lab(a, &mut lab_a);
+        Self::color_to_lab(b, &mut lab_b);
+        Self::lab_distance_euclidean(&lab_a, &lab_b)
+    }
+
+    /// Returns the Euclidean distance between two CIE Lab colors, the CIE76 color difference.
+    pub fn lab_distance_euclidean(lab1: &[f64;3], lab2: &[f64;3]) -> f64 {
+        lab1.iter().zip(lab2).map(|(a, b)| (a - b) * (a - b)).sum::<f64>().sqrt()
+    }
+
+    /// Returns the CIEDE2000 color difference between two colors. The alpha components are
+    /// ignored. See [lab_distance_ciede2000][ColorUtils::lab_distance_ciede2000].
+    pub fn distance_ciede2000(a: ColorInt, b: ColorInt) -> f64 {
+        let (mut lab_a, mut lab_b) = ([0f64;3], [0f64;3]);
+        Self::color_to_lab(a, &mut lab_a);
+        Self::color_to_lab(b, &mut lab_b);
+        Self::lab_distance_ciede2000(&lab_a, &lab_b)
+    }
+
+    /// Returns the CIEDE2000 color difference between two CIE Lab colors, with the parametric
+    /// weighting factors `kL`, `kC` and `kH` all set to 1.
+    ///
+    /// Follows "The CIEDE2000 Color-Difference Formula: Implementation Notes, Supplementary Test
+    /// Data, and Mathematical Observations" by Sharma, Wu and Dalal.
+    pub fn lab_distance_ciede2000(lab1: &[f64;3], lab2: &[f64;3]) -> f64 {
+        const POW25_7: f64 = 6103515625f64;
+
+        let [l1, a1, b1] = *lab1;
+        let [l2, a2, b2] = *lab2;
+
+        let c_bar = (f64::hypot(a1, b1) + f64::hypot(a2, b2)) / 2f64;
+        let g = 0.5 * (1f64 - f64::sqrt(c_bar.powi(7) / (c_bar.powi(7) + POW25_7)));
+        let a1p = (1f64 + g) * a1;
+        let a2p = (1f64 + g) * a2;
+        let c1p = f64::hypot(a1p, b1);
+        let c2p = f64::hypot(a2p, b2);
+        let hue = |b: f64, a: f64| if a == 0f64 && b == 0f64 { 0f64 } else { b.atan2(a).to_degrees().rem_euclid(360f64) };
+        let h1p = hue(b1, a1p);
+        let h2p = hue(b2, a2p);
+
+        let delta_lp = l2 - l1;
+        let delta_cp = c2p - c1p;
+        let delta_hp = if c1p * c2p == 0f64 {
+            0f64
+        } else if h2p - h1p > 180f64 {
+            h2p - h1p - 360f64
+        } else if h2p - h1p < -180f64 {
+            h2p - h1p + 360f64
+        } else {
+            h2p - h1p
+        };
+        let delta_big_hp = 2f64 * f64::sqrt(c1p * c2p) * (delta_hp / 2f64).to_radians().sin();
+
+        let l_bar_p = (l1 + l2) / 2f64;
+        let c_bar_p = (c1p + c2p) / 2f64;
+        let h_bar_p = if c1p * c2p == 0f64 {
+            h1p + h2p
+        } else if (h1p - h2p).abs() <= 180f64 {
+            (h1p + h2p) / 2f64
+        } else if h1p + h2p < 360f64 {
+            (h1p + h2p + 360f64) / 2f64
+        } else {
+            (h1p + h2p - 360f64) / 2f64
+        };
+
+        let t = 1f64 - 0.17 * (h_bar_p - 30f64).to_radians().cos()
+            + 0.24 * (2f64 * h_bar_p).to_radians().cos()
+            + 0.32 * (3f64 * h_bar_p + 6f64).to_radians().cos()
+            - 0.20 * (4f64 * h_bar_p - 63f64).to_radians().cos();
+        let delta_theta = 30f64 * f64::exp(-((h_bar_p - 275f64) / 25f64).powi(2));
+        let r_c = 2f64 * f64::sqrt(c_bar_p.powi(7) / (c_bar_p.powi(7) + POW25_7));
+        let s_l = 1f64 + 0.015 * (l_bar_p - 50f64).powi(2) / f64::sqrt(20f64 + (l_bar_p - 50f64).powi(2));
+        let s_c = 1f64 + 0.045 * c_bar_p;
+        let s_h = 1f64 + 0.015 * c_bar_p * t;
+        let r_t = -(2f64 * delta_theta).to_radians().sin() * r_c;
+
+        let l = delta_lp / s_l;
+        let c = delta_cp / s_c;
+        let h = delta_big_hp / s_h;
+        f64::sqrt(l * l + c * c + h * h + r_t * c * h)
+    }
+
+    /// Returns `true` if the red, green and blue components of `color` are all within
+    /// `tolerance` of each other. Alpha is ignored.
+    pub fn is_grayscale(color: ColorInt, tolerance: u8) -> bool {
+        let (r, g, b) = (color.red(), color.green(), color.blue());
+        r.max(g).max(b) - r.min(g).min(b) <= tolerance
+    }
+
     /// Set the alpha component of color to be alpha.
     pub fn set_alpha_component(color: ColorInt, alpha: u8) -> ColorInt {
         (color & 0x00ffffff) | ((alpha as i32) << 24)
@@ -234,7 +515,7 @@ impl ColorUtils {
 
         while num_iterations <= ColorUtils::MIN_ALPHA_SEARCH_MAX_ITERATIONS &&
                 i32::from(max_alpha - min_alpha) > ColorUtils::MIN_ALPHA_SEARCH_PRECISION {
-            let test_alpha = ((min_alpha + max_alpha) / 2) as u8;
+            let test_alpha = ((min_alpha as u16 + max_alpha as u16) / 2) as u8;
 
             test_foreground = Self::set_alpha_component(foreground, test_alpha);
             test_ratio = Self::calculate_contrast(test_foreground, background);
@@ -252,4 +533,160 @@ impl ColorUtils {
         return max_alpha.into();
     }
 
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_bulk_conversions_match_scalar() {
+    let colors = [
+        Color::rgb(255, 0, 0), Color::rgb(12, 200, 97), Color::rgb(128, 128, 128),
+        Color::rgb(30, 60, 220), Color::rgb(0, 0, 0), Color::rgb(255, 255, 255),
+    ];
+
+    let hsl = ColorUtils::colors_to_hsl(&colors);
+    let lab = ColorUtils::colors_to_lab(&colors);
+    let back = ColorUtils::hsl_to_colors(&hsl);
+    assert_eq!(hsl.len(), colors.len());
+
+    for (i, &color) in colors.iter().enumerate() {
+        let mut expected_hsl = [0f32;3];
+        ColorUtils::color_to_hsl(color, &mut expected_hsl);
+        assert_eq!(hsl[i], expected_hsl);
+
+        let mut expected_lab = [0f64;3];
+        ColorUtils::color_to_lab(color, &mut expected_lab);
+        assert_eq!(lab[i], expected_lab);
+
+        assert_eq!(back[i], ColorUtils::hsl_to_rgb(hsl[i]));
+    }
+}
+
+#[test]
+fn test_is_grayscale() {
+    assert!(ColorUtils::is_grayscale(Color::rgb(128, 128, 128), 0));
+    assert!(ColorUtils::is_grayscale(Color::argb(0, 0, 0, 0), 0));
+
+    let tinted = Color::rgb(128, 131, 126);
+    assert!(!ColorUtils::is_grayscale(tinted, 0));
+    assert!(!ColorUtils::is_grayscale(tinted, 4));
+    assert!(ColorUtils::is_grayscale(tinted, 5));
+}
+
+#[test]
+fn test_ciede2000_reference_pairs() {
+    // Pairs 1, 7 and 17 of the Sharma, Wu and Dalal test data.
+    let pairs = [
+        ([50.0, 2.6772, -79.7751], [50.0, 0.0, -82.7485], 2.0425),
+        ([50.0, 0.0, 0.0], [50.0, -1.0, 2.0], 2.3669),
+        ([50.0, 2.5, 0.0], [73.0, 25.0, -18.0], 27.1492),
+    ];
+    for (lab1, lab2, expected) in pairs {
+        assert!((ColorUtils::lab_distance_ciede2000(&lab1, &lab2) - expected).abs() < 1e-4);
+        assert!((ColorUtils::lab_distance_ciede2000(&lab2, &lab1) - expected).abs() < 1e-4);
+    }
+    assert_eq!(ColorUtils::distance_ciede2000(Color::rgb(12, 200, 97), Color::rgb(12, 200, 97)), 0f64);
+}
+
+#[test]
+fn test_minimum_alpha_past_u8_midpoint() {
+    // White needs more than half opacity for 15:1 on black, so the search bounds sum past 255
+    let alpha = ColorUtils::calculate_minimum_alpha(Color::WHITE, Color::BLACK, 15.0);
+    assert!(alpha > 127);
+    let foreground = ColorUtils::set_alpha_component(Color::WHITE, alpha as u8);
+    assert!(ColorUtils::calculate_contrast(foreground, Color::BLACK) >= 15.0);
+}
+
+#[test]
+fn test_hue_wheel() {
+    assert!(ColorUtils::hue_wheel(0, 1.0, 0.5).is_empty());
+
+    // Hues 0, 60, 120, 180, 240 and 300.
+    let wheel = ColorUtils::hue_wheel(6, 1.0, 0.5);
+    assert_eq!(wheel, vec![
+        Color::rgb(255, 0, 0), Color::rgb(255, 255, 0), Color::rgb(0, 255, 0),
+        Color::rgb(0, 255, 255), Color::rgb(0, 0, 255), Color::rgb(255, 0, 255),
+    ]);
+
+    // Every hue is 360 / count degrees after the previous one.
+    let count = 10;
+    let wheel = ColorUtils::hue_wheel(count, 0.8, 0.4);
+    for (i, &color) in wheel.iter().enumerate() {
+        let hue = i as f32 * 360.0 / count as f32;
+        assert_eq!(color, ColorUtils::hsl_to_rgb([hue, 0.8, 0.4]));
+    }
+}
+
+#[test]
+fn test_hsl_round_trip() {
+    let colors = [
+        Color::rgb(0, 0, 0), Color::rgb(255, 255, 255), Color::rgb(128, 128, 128),
+        Color::rgb(255, 0, 0), Color::rgb(255, 255, 0), Color::rgb(12, 200, 97),
+        Color::rgb(80, 30, 200), Color::rgb(250, 128, 114), Color::rgb(1, 2, 3),
+    ];
+    for color in colors {
+        let mut hsl = [0f32;3];
+        ColorUtils::color_to_hsl(color, &mut hsl);
+        let round_trip = ColorUtils::hsl_to_rgb(hsl);
+        for (a, b) in [
+            (color.red(), round_trip.red()),
+            (color.green(), round_trip.green()),
+            (color.blue(), round_trip.blue()),
+        ] {
+            assert!(a.abs_diff(b) <= 1, "{hsl:?}");
+        }
+        assert_eq!(round_trip.alpha(), 255);
+    }
+
+    assert_eq!(ColorUtils::hsl_to_color(200.0, 0.0, 0.5), Color::rgb(128, 128, 128));
+    assert_eq!(ColorUtils::hsl_to_color(-120.0, 1.0, 0.5), Color::rgb(0, 0, 255));
+}
+
+#[test]
+fn test_rgb_to_hsl_known_values() {
+    let cases = [
+        ((255, 0, 0), [0.0, 1.0, 0.5]),
+        ((128, 128, 128), [0.0, 0.0, 0.50196]),
+        // Red is the maximum but not the minimum channel.
+        ((250, 128, 114), [6.17647, 0.93151, 0.71373]),
+    ];
+    for ((r, g, b), expected) in cases {
+        let mut hsl = [0f32;3];
+        ColorUtils::rgb_to_hsl(r, g, b, &mut hsl);
+        for (actual, expected) in hsl.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-4, "{:?} != {:?}", hsl, expected);
+        }
+    }
+}
+
+#[test]
+fn test_lab_round_trip() {
+    let samples = [
+        [0.0, 0.0, 0.0], [95.047, 100.0, 108.883], [41.24, 21.26, 1.93], [20.0, 30.0, 60.0], [0.5, 0.4, 0.3],
+    ];
+    for xyz in samples {
+        let (mut lab, mut back) = ([0f64;3], [0f64;3]);
+        ColorUtils::xyz_to_lab(xyz[0], xyz[1], xyz[2], &mut lab);
+        assert!((0.0..=100.0).contains(&lab[0]));
+        ColorUtils::lab_to_xyz(lab[0], lab[1], lab[2], &mut back);
+        for (a, b) in xyz.iter().zip(back) {
+            assert!((a - b).abs() < 1e-9, "{xyz:?} -> {lab:?} -> {back:?}");
+        }
+    }
+
+    let colors = [Color::rgb(0, 0, 0), Color::rgb(255, 255, 255), Color::rgb(12, 200, 97), Color::rgb(250, 128, 114)];
+    for color in colors {
+        let mut lab = [0f64;3];
+        ColorUtils::color_to_lab(color, &mut lab);
+        assert_eq!(ColorUtils::lab_to_rgb(lab), color);
+    }
+}
+
+#[test]
+fn test_distance_ciede() {
+    let black = Color::rgb(0, 0, 0);
+    let white = Color::rgb(255, 255, 255);
+    assert!((ColorUtils::distance_ciede(black, white) - 100.0).abs() < 1e-3);
+    assert_eq!(ColorUtils::distance_ciede(white, white), 0.0);
+    assert!(ColorUtils::distance_ciede(Color::rgb(200, 40, 40), Color::rgb(201, 40, 40)) < 2.3);
+    assert!(ColorUtils::distance_ciede(Color::rgb(200, 40, 40), Color::rgb(40, 40, 200)) > 2.3);
+    assert_eq!(ColorUtils::lab_distance_euclidean(&[50.0, 0.0, 0.0], &[50.0, 3.0, 4.0]), 5.0);
+}
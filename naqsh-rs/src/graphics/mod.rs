@@ -6,10 +6,15 @@ mod color_int;
 mod color_utils;
 mod palette;
 mod target;
+mod text;
+mod theme;
 
+pub use canvas::*;
 pub use color::*;
 pub use color_cut_quantizer::*;
 pub use color_int::*;
 pub use color_utils::*;
 pub use palette::*;
-pub use target::*;
\ No newline at end of file
+pub use target::*;
+pub use text::*;
+pub use theme::*;
\ No newline at end of file
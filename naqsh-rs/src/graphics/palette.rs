@@ -1,6 +1,10 @@
-use std::collections::HashMap;
+use std::cell::OnceCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::fmt;
 
-use crate::graphics::{Color, ColorUtils, Target, TargetKind};
+use crate::graphics::{Color, ColorCutQuantizer, ColorUtils, Target, TargetKind};
 use crate::util::SparseBooleanArray;
 use crate::image::Image;
 use crate::object::Rect;
@@ -19,7 +23,9 @@ pub struct Swatch {
     m_generated_text_colors: bool,
     m_title_text_color: ColorInt,
     m_body_text_color: ColorInt,
-    m_hsl: [f32;3]
+    m_hsl: [f32;3],
+    m_min_contrast_title_text: f32,
+    m_min_contrast_body_text: f32
 }
 
 impl Swatch {
@@ -27,14 +33,18 @@ impl Swatch {
     const MIN_CONTRAST_BODY_TEXT: f32 = 4.5;
 
     pub fn new(color: ColorInt, population: i32) -> Swatch {
-        Swatch {
+        let mut swatch = Swatch {
             m_red: Color::red(color),
             m_green: Color::green(color),
             m_blue: Color::blue(color),
             m_rgb: color,
             m_population: population,
+            m_min_contrast_title_text: Self::MIN_CONTRAST_TITLE_TEXT,
+            m_min_contrast_body_text: Self::MIN_CONTRAST_BODY_TEXT,
             ..Default::default()
-        }
+        };
+        ColorUtils::rgb_to_hsl(swatch.m_red, swatch.m_green, swatch.m_blue, &mut swatch.m_hsl);
+        swatch
     }
 
     /// Returns this swatch's RGB color value
@@ -49,8 +59,7 @@ impl Swatch {
     /// hsv\[1\] is Saturation \[0...1\]
     ///
     /// hsv\[2\] is Lightness \[0...1\]
-    pub fn get_hsl(&mut self) -> [f32;3] {
-        ColorUtils::rgb_to_hsl(self.m_red, self.m_green, self.m_blue, &mut self.m_hsl);
+    pub fn get_hsl(&self) -> [f32;3] {
         self.m_hsl
     }
 
@@ -59,14 +68,22 @@ impl Swatch {
         self.m_population
     }
 
+    /// Set the minimum contrast ratios the title and body text colors must reach against this
+    /// swatch. Defaults to 3.0 for title and 4.5 for body text; use 4.5 and 7.0 for WCAG AAA.
+    pub fn set_text_contrast(&mut self, title: f32, body: f32) {
+        self.m_min_contrast_title_text = title;
+        self.m_min_contrast_body_text = body;
+        self.m_generated_text_colors = false;
+    }
+
     fn ensure_text_colors_generated(&mut self) {
         if !self.m_generated_text_colors {
             // First check white, as most colors will be dark
 
             let light_body_alpha = ColorUtils::calculate_minimum_alpha(
-                    Color::WHITE, self.m_rgb, Self::MIN_CONTRAST_BODY_TEXT);
+                    Color::WHITE, self.m_rgb, self.m_min_contrast_body_text);
             let light_title_alpha = ColorUtils::calculate_minimum_alpha(
-                    Color::WHITE, self.m_rgb, Self::MIN_CONTRAST_TITLE_TEXT);
+                    Color::WHITE, self.m_rgb, self.m_min_contrast_title_text);
 
             if light_body_alpha != -1 && light_title_alpha != -1 {
                 // If we found valid light values, use them and return
@@ -77,9 +94,9 @@ impl Swatch {
             }
 
             let dark_body_alpha = ColorUtils::calculate_minimum_alpha(
-                    Color::BLACK, self.m_rgb, Self::MIN_CONTRAST_BODY_TEXT);
+                    Color::BLACK, self.m_rgb, self.m_min_contrast_body_text);
             let dark_title_alpha = ColorUtils::calculate_minimum_alpha(
-                    Color::BLACK, self.m_rgb, Self::MIN_CONTRAST_TITLE_TEXT);
+                    Color::BLACK, self.m_rgb, self.m_min_contrast_title_text);
 
             if dark_body_alpha != -1 && dark_title_alpha != -1 {
                 // If we found valid dark values, use them and return
@@ -125,6 +142,18 @@ impl Swatch {
     }
 }
 
+impl From<&Swatch> for ColorInt {
+    fn from(swatch: &Swatch) -> Self {
+        swatch.m_rgb
+    }
+}
+
+impl From<&Swatch> for (u8, u8, u8) {
+    fn from(swatch: &Swatch) -> Self {
+        (swatch.m_red, swatch.m_green, swatch.m_blue)
+    }
+}
+
 /// A Filter provides a mechanism for exercising fine-grained control over which colors
 /// are valid within a resulting [Palette].
 pub trait Filter {
@@ -149,7 +178,7 @@ impl DefaultFilter {
     }
 
     fn is_white(&self, hsl_color: [f32;3]) -> bool {
-        hsl_color[2] <= Self::WHITE_MIN_LIGHTNESS
+        hsl_color[2] >= Self::WHITE_MIN_LIGHTNESS
     }
 
     fn is_near_red_iline(&self, hsl_color: [f32;3]) -> bool {
@@ -164,6 +193,41 @@ impl Filter for DefaultFilter {
     }
 }
 
+/// Builder for the list of [Filter]s used by [ColorCutQuantizer](super::ColorCutQuantizer) and
+/// [PaletteBuilder].
+///
+/// <pre>
+/// let filters = FilterSet::new().default().add(MyFilter).build();
+/// </pre>
+#[derive(Default)]
+pub struct FilterSet {
+    filters: Vec<Box<dyn Filter>>
+}
+
+impl FilterSet {
+    /// Creates an empty filter set.
+    pub fn new() -> Self {
+        Self { filters: vec![] }
+    }
+
+    /// Adds `filter` to the set.
+    #[allow(clippy::should_implement_trait)]
+    pub fn add<F: Filter + 'static>(mut self, filter: F) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Adds the default filter, which removes near-black, near-white and near red-i-line colors.
+    pub fn default(self) -> Self {
+        self.add(DefaultFilter())
+    }
+
+    /// Returns the filters in the order they were added.
+    pub fn build(self) -> Vec<Box<dyn Filter>> {
+        self.filters
+    }
+}
+
 /// A helper struct to extract prominent colors from an image.
 ///
 /// A number of colors with different profiles are extracted from the image:
@@ -207,13 +271,217 @@ pub struct Palette {
 
 impl Palette {
     pub fn new(swatches: Vec<Swatch>, targets: Vec<Target>) -> Self {
+        Self::with_dominant_exclusion(swatches, targets, false)
+    }
+
+    /// Creates a palette like [Palette::new]. When `exclude_dominant` is true, the dominant
+    /// swatch is marked as used before selection so no target selects it.
+    fn with_dominant_exclusion(swatches: Vec<Swatch>, targets: Vec<Target>, exclude_dominant: bool) -> Self {
         let swatch = Self::find_dominant_swatch(&swatches);
-        Self {
+        let mut palette = Self {
             m_swatches: swatches,
             m_targets: targets,
             m_selected_swatches: HashMap::new(),
             m_used_colors: SparseBooleanArray::default(),
             m_dominant_swatch: swatch
+        };
+        if exclude_dominant && !palette.m_swatches.is_empty() {
+            palette.m_used_colors.append(*palette.m_dominant_swatch.get_rgb(), true);
+        }
+        palette.generate();
+        palette
+    }
+
+    /// Selects the best scoring swatch for each target, in the order the targets were added.
+    fn generate(&mut self) {
+        let mut i = 0;
+        let count = self.m_targets.len();
+        while i < count {
+            let target = self.m_targets[i].clone();
+            // Score against normalized weights, but keep the map keyed by the target as given
+            let mut scoring_target = target.clone();
+            scoring_target.normalize_weights();
+            if let Some(swatch) = self.generate_scored_target(&scoring_target) {
+                self.m_selected_swatches.insert(target, swatch);
+            }
+            i += 1;
+        }
+        // We have finished generating the target swatches, so clear the used colors
+        self.m_used_colors.clear();
+    }
+
+    fn generate_scored_target(&mut self, target: &Target) -> Option<Swatch> {
+        let max_score_swatch = self.get_max_scored_swatch_for_target(target).cloned();
+        if let Some(swatch) = &max_score_swatch {
+            if target.is_exclusive() {
+                // If we have a swatch, and the target is exclusive, add the color to the used list
+                self.m_used_colors.append(*swatch.get_rgb(), true);
+            }
+        }
+        max_score_swatch
+    }
+
+    fn get_max_scored_swatch_for_target(&self, target: &Target) -> Option<&Swatch> {
+        let mut max_score = 0f32;
+        let mut max_score_swatch = None;
+        for swatch in &self.m_swatches {
+            if self.should_be_scored_for_target(swatch, target) {
+                let score = self.generate_score(swatch, target);
+                if max_score_swatch.is_none() || score > max_score {
+                    max_score_swatch = Some(swatch);
+                    max_score = score;
+                }
+            }
+        }
+        max_score_swatch
+    }
+
+    fn should_be_scored_for_target(&self, swatch: &Swatch, target: &Target) -> bool {
+        // Check whether the HSL values are within the correct ranges, and this color hasn't
+        // been used yet.
+        let hsl = swatch.get_hsl();
+        hsl[1] >= target.get_minimum_saturation() && hsl[1] <= target.get_maximum_saturation()
+            && hsl[2] >= target.get_minimum_lightness() && hsl[2] <= target.get_maximum_lightness()
+            && !self.m_used_colors.get_or_false(*swatch.get_rgb())
+    }
+
+    fn generate_score(&self, swatch: &Swatch, target: &Target) -> f32 {
+        let hsl = swatch.get_hsl();
+
+        let mut saturation_score = 0f32;
+        let mut luminance_score = 0f32;
+        let mut population_score = 0f32;
+        let mut contrast_score = 0f32;
+
+        let max_population = self.m_dominant_swatch.get_population().max(1);
+
+        if target.get_saturation_weight() > 0f32 {
+            saturation_score = target.get_saturation_weight()
+                * (1f32 - f32::abs(hsl[1] - target.get_target_saturation()));
+        }
+        if target.get_lightness_weight() > 0f32 {
+            luminance_score = target.get_lightness_weight()
+                * (1f32 - f32::abs(hsl[2] - target.get_target_lightness()));
+        }
+        if target.get_population_weight() > 0f32 {
+            population_score = target.get_population_weight()
+                * (swatch.get_population() as f32 / max_population as f32);
+        }
+        if let Some(background) = target.get_contrast_background() {
+            // Contrast ratios range from 1 to 21, scale them to [0, 1]
+            let contrast = ColorUtils::calculate_contrast(
+                ColorUtils::set_alpha_component(swatch.get_rgb(), 255), background);
+            contrast_score = (contrast as f32 - 1f32) / 20f32;
+        }
+
+        saturation_score + luminance_score + population_score + contrast_score
+    }
+
+    /// Returns a [PaletteView] borrowing this palette.
+    pub fn view(&self) -> PaletteView<'_> {
+        PaletteView { palette: self, selected: OnceCell::new() }
+    }
+
+    /// Returns the selected swatch for the given target from the palette, or `None` if one
+    /// could not be found.
+    pub fn get_swatch_for_target(&self, target: &Target) -> Option<&Swatch> {
+        self.m_selected_swatches.get(target)
+    }
+
+    /// Returns the selected swatch for the target called `name`, see [Target::named].
+    pub fn get_swatch_for_name(&self, name: &str) -> Option<&Swatch> {
+        self.get_swatch_for_target(&Target::named(name)?)
+    }
+
+    /// Returns the most populous swatch whose hue is within `tolerance` degrees of `hue`, going
+    /// around the color wheel, so a hue of 355 is 10 degrees away from 5.
+    pub fn swatch_near_hue(&self, hue: f32, tolerance: f32) -> Option<&Swatch> {
+        self.m_swatches.iter()
+            .filter(|swatch| {
+                let distance = (swatch.get_hsl()[0] - hue).rem_euclid(360f32);
+                distance.min(360f32 - distance) <= tolerance
+            })
+            .max_by_key(|swatch| swatch.get_population())
+    }
+
+    /// Returns the CIEDE2000 distance between the swatches this palette and `other` selected
+    /// for each [TargetKind]. Kinds without a selected swatch in either palette are left out.
+    pub fn diff(&self, other: &Palette) -> Vec<(TargetKind, f64)> {
+        TargetKind::ALL.iter().filter_map(|&kind| {
+            let target = Target::new(kind);
+            let a = self.get_swatch_for_target(&target)?;
+            let b = other.get_swatch_for_target(&target)?;
+            Some((kind, ColorUtils::distance_ciede2000(a.get_rgb(), b.get_rgb())))
+        }).collect()
+    }
+
+    /// Returns the swatches of this palette as a GIMP palette (`.gpl`) called `name`. Each color
+    /// is named after its hex value.
+    pub fn to_gpl(&self, name: &str) -> String {
+        let mut gpl = format!("GIMP Palette\nName: {name}\nColumns: 0\n#\n");
+        for swatch in &self.m_swatches {
+            let rgb = swatch.get_rgb();
+            gpl.push_str(&format!("{:3} {:3} {:3}\t#{:06x}\n",
+                rgb.red(), rgb.green(), rgb.blue(), *rgb as u32 & 0xffffff));
+        }
+        gpl
+    }
+
+    /// Returns the swatches of this palette as an Adobe Swatch Exchange (`.ase`) file. Each color
+    /// is stored as a global RGB color named after its hex value.
+    pub fn to_ase(&self) -> Vec<u8> {
+        let mut ase = b"ASEF".to_vec();
+        ase.extend_from_slice(&1u16.to_be_bytes());
+        ase.extend_from_slice(&0u16.to_be_bytes());
+        ase.extend_from_slice(&(self.m_swatches.len() as u32).to_be_bytes());
+
+        for swatch in &self.m_swatches {
+            let rgb = swatch.get_rgb();
+            // Names are null-terminated UTF-16
+            let name: Vec<u16> = format!("#{:06x}", *rgb as u32 & 0xffffff).encode_utf16().chain([0]).collect();
+
+            let mut block = (name.len() as u16).to_be_bytes().to_vec();
+            name.iter().for_each(|unit| block.extend_from_slice(&unit.to_be_bytes()));
+            block.extend_from_slice(b"RGB ");
+            for channel in [rgb.red(), rgb.green(), rgb.blue()] {
+                block.extend_from_slice(&(channel as f32 / 255f32).to_be_bytes());
+            }
+            // Color type: 0 global, 1 spot, 2 normal
+            block.extend_from_slice(&0u16.to_be_bytes());
+
+            // Block type: color entry
+            ase.extend_from_slice(&1u16.to_be_bytes());
+            ase.extend_from_slice(&(block.len() as u32).to_be_bytes());
+            ase.extend_from_slice(&block);
+        }
+        ase
+    }
+
+    /// Returns the color of the first of `kinds` which has a selected swatch. If none of them
+    /// do, the dominant color is returned, or `default` if the palette has no swatches.
+    ///
+    /// For example, `[Vibrant, LightVibrant]` falls back to the light vibrant color when the
+    /// palette has no vibrant swatch.
+    pub fn get_color_with_fallback(&self, kinds: &[TargetKind], default: ColorInt) -> ColorInt {
+        kinds.iter()
+            .find_map(|&kind| self.get_swatch_for_target(&Target::new(kind)))
+            .map(Swatch::get_rgb)
+            .or_else(|| (!self.m_swatches.is_empty()).then(|| self.m_dominant_swatch.get_rgb()))
+            .unwrap_or(default)
+    }
+
+    /// Returns the swatches selected for all six preset targets and the dominant swatch at once.
+    pub fn swatches_by_role(&self) -> RoleSwatches<'_> {
+        let [light_vibrant, vibrant, dark_vibrant, light_muted, muted, dark_muted] =
+            TargetKind::ALL.map(|kind| self.get_swatch_for_target(&Target::new(kind)));
+        RoleSwatches {
+            light_vibrant,
+            vibrant,
+            dark_vibrant,
+            light_muted,
+            muted,
+            dark_muted,
+            dominant: (!self.m_swatches.is_empty()).then_some(&self.m_dominant_swatch),
         }
     }
 
@@ -226,16 +494,20 @@ impl Palette {
         &self.m_targets
     }
 
-    fn find_dominant_swatch(swatches: &Vec<Swatch>) -> Swatch {
+    /// Returns the swatch with the highest population. Ties are broken by picking the swatch
+    /// with the lowest ARGB value, so the result does not depend on the order of `swatches`.
+    fn find_dominant_swatch(swatches: &[Swatch]) -> Swatch {
         let mut max_pop = i32::MIN;
         let mut max_swatch = Swatch::default();
         let mut i = 0;
         let count = swatches.len();
         while i < count {
             let swatch = swatches.get(i).unwrap();
-            if swatch.get_population() > max_pop {
+            let population = swatch.get_population();
+            if population > max_pop
+                || (population == max_pop && (*swatch.get_rgb() as u32) < (*max_swatch.get_rgb() as u32)) {
                 max_swatch = swatch.clone();
-                max_pop = swatch.get_population();
+                max_pop = population;
             }
 
             i += 1;
@@ -245,6 +517,42 @@ impl Palette {
     }
 }
 
+/// A borrowed view of a [Palette] which hands out references to the palette's own swatches
+/// instead of copies. The swatch selected for each target is looked up once, on first use.
+pub struct PaletteView<'a> {
+    palette: &'a Palette,
+    selected: OnceCell<HashMap<&'a Target, &'a Swatch>>,
+}
+
+impl<'a> PaletteView<'a> {
+    fn selected(&self) -> &HashMap<&'a Target, &'a Swatch> {
+        let palette = self.palette;
+        self.selected.get_or_init(|| {
+            palette.m_targets.iter().filter_map(|target| {
+                let rgb = palette.m_selected_swatches.get(target)?.get_rgb();
+                let swatch = palette.m_swatches.iter().find(|swatch| swatch.get_rgb() == rgb)?;
+                Some((target, swatch))
+            }).collect()
+        })
+    }
+
+    /// Returns the swatch selected for `target`, or `None` if one could not be found.
+    pub fn get_swatch_for_target(&self, target: &Target) -> Option<&'a Swatch> {
+        self.selected().get(target).copied()
+    }
+
+    /// Returns the swatch selected for the preset target of `kind`.
+    pub fn get(&self, kind: TargetKind) -> Option<&'a Swatch> {
+        self.get_swatch_for_target(&Target::new(kind))
+    }
+
+    /// Returns the most populous swatch, or `None` if the palette has no swatches.
+    pub fn dominant_swatch(&self) -> Option<&'a Swatch> {
+        let rgb = self.palette.m_dominant_swatch.get_rgb();
+        self.palette.m_swatches.iter().find(|swatch| swatch.get_rgb() == rgb)
+    }
+}
+
 impl From<Vec<Swatch>> for Palette {
     /// Generate a [Palette] from the pre-generated list of [Swatch] swatches.
     /// This is useful for testing, or if you want to resurrect a [Palette] instance from a
@@ -254,16 +562,69 @@ impl From<Vec<Swatch>> for Palette {
     }
 }
 
+/// Errors returned by [PaletteBuilder::generate].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaletteError {
+    /// The input image has more pixels than allowed by [PaletteBuilder::max_input_pixels].
+    TooManyPixels { pixels: usize, max: usize },
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaletteError::TooManyPixels { pixels, max } => {
+                write!(f, "input image has {pixels} pixels, more than the allowed {max}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PaletteError {}
+
+/// The swatches a [Palette] selected for each preset [TargetKind], and its dominant swatch,
+/// see [Palette::swatches_by_role]. Roles without a selected swatch are `None`.
+#[derive(Clone, Copy)]
+pub struct RoleSwatches<'a> {
+    pub light_vibrant: Option<&'a Swatch>,
+    pub vibrant: Option<&'a Swatch>,
+    pub dark_vibrant: Option<&'a Swatch>,
+    pub light_muted: Option<&'a Swatch>,
+    pub muted: Option<&'a Swatch>,
+    pub dark_muted: Option<&'a Swatch>,
+    /// The most populous swatch, `None` only if the palette has no swatches.
+    pub dominant: Option<&'a Swatch>,
+}
+
+impl<'a> RoleSwatches<'a> {
+    /// Returns the swatch selected for `kind`.
+    pub fn get(&self, kind: TargetKind) -> Option<&'a Swatch> {
+        match kind {
+            TargetKind::LightVibrant => self.light_vibrant,
+            TargetKind::Vibrant => self.vibrant,
+            TargetKind::DarkVibrant => self.dark_vibrant,
+            TargetKind::LightMuted => self.light_muted,
+            TargetKind::Muted => self.muted,
+            TargetKind::DarkMuted => self.dark_muted,
+        }
+    }
+}
+
 /// PaletteBuilder class for generating [Palette] instances.
 pub struct PaletteBuilder {
     m_swatches: Vec<Swatch>,
     m_image: Image,
+    m_images: Vec<Image>,
     m_targets: Vec<Target>,
     m_max_colors: i32,
+    m_min_box_population: i32,
+    m_exclude_dominant: bool,
     m_resize_area: i32,
     m_resize_max_dimension: i32,
     m_filters: Vec<Box<dyn Filter>>,
-    m_region: Rect
+    m_default_filter: bool,
+    m_region: Rect,
+    m_max_input_pixels: Option<usize>,
+    m_text_contrast: Option<(f32, f32)>
 }
 
 impl PaletteBuilder {
@@ -273,7 +634,7 @@ impl PaletteBuilder {
     pub fn new(image: Image) -> Self {
         let mut builder = Self::default();
         builder.m_image = image;
-        builder.m_filters.push(Box::new(DefaultFilter()));
+        builder.m_default_filter = true;
 
         // Add the default targets
         builder.m_targets.push(Target::new(TargetKind::LightVibrant));
@@ -285,6 +646,274 @@ impl PaletteBuilder {
 
         builder
     }
+
+    /// Set the maximum number of colors to use in the quantization step when generating the
+    /// palette. Good values depend on the source image: 8 to 16 for landscapes, 24 or more for
+    /// images with faces. Defaults to 16.
+    pub fn maximum_color_count(mut self, colors: i32) -> Self {
+        self.m_max_colors = colors;
+        self
+    }
+
+    /// Set the resize value when using an [Image] as the source. If the image's area is greater
+    /// than `area`, it is scaled down so that its area is roughly `area`, keeping the aspect
+    /// ratio. A value of 0 or less disables resizing. Replaces any maximum dimension set with
+    /// [PaletteBuilder::resize_image_max_dimension]. Defaults to `112 * 112`.
+    pub fn resize_bitmap_area(mut self, area: i32) -> Self {
+        self.m_resize_area = area;
+        self.m_resize_max_dimension = -1;
+        self
+    }
+
+    /// Set the maximum size of the longest side of the source image. Larger images are scaled
+    /// down, keeping the aspect ratio. A value of 0 or less disables resizing. Replaces any
+    /// area set with [PaletteBuilder::resize_bitmap_area].
+    pub fn resize_image_max_dimension(mut self, max: i32) -> Self {
+        self.m_resize_max_dimension = max;
+        self.m_resize_area = -1;
+        self
+    }
+
+    /// Set a region of the images to be used exclusively when calculating the palette, in the
+    /// coordinates of the original images. An empty region uses the whole image.
+    pub fn set_region(mut self, left: i32, top: i32, right: i32, bottom: i32) -> Self {
+        self.m_region = Rect::new(left, top, right, bottom);
+        self
+    }
+
+    /// Clear any region previously set via [PaletteBuilder::set_region].
+    pub fn clear_region(mut self) -> Self {
+        self.m_region = Rect::default();
+        self
+    }
+
+    /// Add a filter to be able to have fine grained control over which colors are allowed in
+    /// the resulting palette.
+    pub fn add_filter(mut self, filter: Box<dyn Filter>) -> Self {
+        self.m_filters.push(filter);
+        self
+    }
+
+    /// Replace all added filters, including the default filter, with those of `filters`.
+    pub fn filters(mut self, filters: FilterSet) -> Self {
+        self.m_filters = filters.build();
+        self.m_default_filter = false;
+        self
+    }
+
+    /// Clear all added filters, including the default filter.
+    pub fn clear_filters(mut self) -> Self {
+        self.m_filters.clear();
+        self.m_default_filter = false;
+        self
+    }
+
+    /// Add a target profile to be generated in the palette. The target is ignored if it is
+    /// already present.
+    pub fn add_target(mut self, target: Target) -> Self {
+        if !self.m_targets.contains(&target) {
+            self.m_targets.push(target);
+        }
+        self
+    }
+
+    /// Skip the default filter, which removes near-black, near-white and near red-i-line colors.
+    /// Any other filters are kept.
+    pub fn without_default_filter(mut self) -> Self {
+        self.m_default_filter = false;
+        self
+    }
+
+    /// Set the minimum number of pixels a color box must hold to be split further, see
+    /// [ColorCutQuantizer::with_min_box_population]. Defaults to 0, which splits every box.
+    pub fn min_box_population(mut self, population: i32) -> Self {
+        self.m_min_box_population = population;
+        self
+    }
+
+    /// Set whether the dominant swatch is kept out of target selection, so the targets do not
+    /// all collapse onto the most populous color. Off by default, as in Android.
+    pub fn exclude_dominant_from_targets(mut self, exclude: bool) -> Self {
+        self.m_exclude_dominant = exclude;
+        self
+    }
+
+    /// Add another image whose pixels are counted together with the builder's image, so a
+    /// single palette spans all of them. Useful for the frames of an animation.
+    pub fn add_image(mut self, image: Image) -> Self {
+        self.m_images.push(image);
+        self
+    }
+
+    /// Set the maximum number of pixels the input images may have in total. [PaletteBuilder::generate]
+    /// rejects larger images with [PaletteError::TooManyPixels] before any pixel data is copied
+    /// or the histogram is allocated.
+    ///
+    /// There is no limit by default.
+    pub fn max_input_pixels(mut self, pixels: usize) -> Self {
+        self.m_max_input_pixels = Some(pixels);
+        self
+    }
+
+    /// Set the minimum contrast ratios used when generating the title and body text colors of
+    /// the swatches, see [Swatch::set_text_contrast].
+    pub fn text_contrast(mut self, title: f32, body: f32) -> Self {
+        self.m_text_contrast = Some((title, body));
+        self
+    }
+
+    /// Generate and return the [Palette] synchronously.
+    pub fn generate(self) -> Result<Palette, PaletteError> {
+        let pixels = std::iter::once(&self.m_image)
+            .chain(&self.m_images)
+            .map(|image| image.width * image.height)
+            .sum();
+        if let Some(max) = self.m_max_input_pixels {
+            if pixels > max {
+                return Err(PaletteError::TooManyPixels { pixels, max });
+            }
+        }
+
+        let mut colors = self.scale_image(&self.m_image).as_color_ints();
+        for image in &self.m_images {
+            colors.extend(self.scale_image(image).as_color_ints());
+        }
+
+        let mut filters = self.m_filters;
+        if self.m_default_filter {
+            filters.insert(0, Box::new(DefaultFilter()));
+        }
+        let quantizer = ColorCutQuantizer::with_min_box_population(
+            colors,
+            self.m_max_colors,
+            filters,
+            self.m_min_box_population
+        );
+
+        let mut swatches = quantizer.get_quantized_colors().clone();
+        if let Some((title, body)) = self.m_text_contrast {
+            swatches.iter_mut().for_each(|swatch| swatch.set_text_contrast(title, body));
+        }
+
+        Ok(Palette::with_dominant_exclusion(swatches, self.m_targets, self.m_exclude_dominant))
+    }
+
+    /// Returns `image` scaled down according to the resize options, and cropped to the region
+    /// if one is set. The region is scaled along with the image.
+    fn scale_image(&self, image: &Image) -> Image {
+        let scaled = if self.m_resize_area > 0 {
+            image.scale_to_fit_area(self.m_resize_area)
+        } else {
+            image.scale_to_max_dimension(self.m_resize_max_dimension)
+        };
+        if self.m_region.is_empty() || image.width == 0 || image.height == 0 {
+            return scaled;
+        }
+
+        let scale_x = scaled.width as f64 / image.width as f64;
+        let scale_y = scaled.height as f64 / image.height as f64;
+        let region = Rect::new(
+            (self.m_region.left as f64 * scale_x).floor() as i32,
+            (self.m_region.top as f64 * scale_y).floor() as i32,
+            (self.m_region.right as f64 * scale_x).ceil() as i32,
+            (self.m_region.bottom as f64 * scale_y).ceil() as i32,
+        );
+        scaled.crop(&region)
+    }
+
+    /// Returns the input pixels and options of this builder, or `None` if it has custom filters,
+    /// which can not be compared.
+    fn cache_key(&self) -> Option<CacheKey> {
+        if !self.m_filters.is_empty() {
+            return None;
+        }
+        let region = &self.m_region;
+        Some(CacheKey {
+            images: std::iter::once(&self.m_image).chain(&self.m_images)
+                .map(|image| (image.width, image.height, image.as_slice().to_vec()))
+                .collect(),
+            targets: self.m_targets.clone(),
+            counts: (self.m_max_colors, self.m_min_box_population),
+            resize: (self.m_resize_area, self.m_resize_max_dimension),
+            region: [region.left, region.top, region.right, region.bottom],
+            flags: (self.m_default_filter, self.m_exclude_dominant),
+            max_input_pixels: self.m_max_input_pixels,
+            text_contrast: self.m_text_contrast.map(|(title, body)| (title.to_bits(), body.to_bits())),
+        })
+    }
+}
+
+/// Input pixels and options of a [PaletteBuilder], kept by [PaletteCache] to tell apart
+/// builders whose hashes collide.
+#[derive(PartialEq, Eq, Hash)]
+struct CacheKey {
+    images: Vec<(usize, usize, Vec<u8>)>,
+    targets: Vec<Target>,
+    counts: (i32, i32),
+    resize: (i32, i32),
+    region: [i32;4],
+    flags: (bool, bool),
+    max_input_pixels: Option<usize>,
+    text_contrast: Option<(u32, u32)>,
+}
+
+/// Memoizes generated [Palette]s for apps which repeatedly request palettes of the same images.
+///
+/// Palettes are looked up by a hash of the builder's input pixels and options, and the input
+/// is compared before a cached palette is reused, so the cache keeps a copy of the input images.
+/// Custom filters can not be compared, so builders with custom filters always generate a new
+/// palette, which is not cached. At most `capacity` palettes are kept; the least recently used
+/// one is dropped first.
+pub struct PaletteCache {
+    capacity: usize,
+    palettes: HashMap<u64, (CacheKey, Palette)>,
+    // Hashes from least to most recently used.
+    order: VecDeque<u64>,
+    // Last palette generated for a builder with custom filters.
+    uncached: Option<Palette>,
+}
+
+impl PaletteCache {
+    /// Creates a cache holding at most `capacity` palettes, and at least one.
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(1), palettes: HashMap::new(), order: VecDeque::new(), uncached: None }
+    }
+
+    /// Returns the cached palette for `builder`'s input, generating and caching it first if
+    /// needed. Builders with custom filters bypass the cache.
+    pub fn get_or_generate(&mut self, builder: PaletteBuilder) -> Result<&Palette, PaletteError> {
+        let Some(key) = builder.cache_key() else {
+            return Ok(self.uncached.insert(builder.generate()?));
+        };
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let hit = self.palettes.get(&hash).is_some_and(|(cached, _)| *cached == key);
+        if !hit {
+            let palette = builder.generate()?;
+            // A colliding input replaces the cached palette in place
+            if !self.palettes.contains_key(&hash) && self.palettes.len() >= self.capacity {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.palettes.remove(&oldest);
+                }
+            }
+            self.palettes.insert(hash, (key, palette));
+        }
+        self.order.retain(|&h| h != hash);
+        self.order.push_back(hash);
+        Ok(&self.palettes[&hash].1)
+    }
+
+    /// Returns the number of cached palettes.
+    pub fn len(&self) -> usize {
+        self.palettes.len()
+    }
+
+    /// Returns true if no palette is cached.
+    pub fn is_empty(&self) -> bool {
+        self.palettes.is_empty()
+    }
 }
 
 impl Default for PaletteBuilder {
@@ -292,12 +921,18 @@ impl Default for PaletteBuilder {
         Self {
             m_swatches: Default::default(),
             m_image: Default::default(),
+            m_images: Default::default(),
             m_targets: Default::default(),
             m_max_colors: Self::DEFAULT_CALCULATE_NUMBER_COLORS,
+            m_min_box_population: 0,
+            m_exclude_dominant: false,
             m_resize_area: Self::DEFAULT_RESIZE_IMAGE_AREA,
             m_resize_max_dimension: -1,
             m_filters: Default::default(),
-            m_region: Default::default()
+            m_default_filter: false,
+            m_region: Default::default(),
+            m_max_input_pixels: None,
+            m_text_contrast: None
         }
     }
 }
@@ -308,3 +943,433 @@ impl From<Image> for PaletteBuilder {
         todo!()
     }
 }
+
+#[test]
+fn test_default_filter_white_threshold() {
+    let filter = DefaultFilter();
+    let hsl = |color| {
+        let mut hsl = [0f32;3];
+        ColorUtils::color_to_hsl(color, &mut hsl);
+        hsl
+    };
+
+    // Only colors at or above the white lightness threshold are treated as white
+    assert!(filter.is_white(hsl(Color::WHITE)));
+    assert!(filter.is_white(hsl(Color::rgb(245, 245, 245))));
+    assert!(!filter.is_white(hsl(Color::rgb(40, 160, 60))));
+    assert!(!filter.is_white(hsl(Color::rgb(128, 128, 128))));
+    assert!(filter.is_allowed(*Color::rgb(40, 160, 60), hsl(Color::rgb(40, 160, 60))));
+}
+
+#[test]
+fn test_filter_set_combines_filters() {
+    struct NotBlue;
+
+    impl Filter for NotBlue {
+        fn is_allowed(&self, _rgb: i32, hsl: [f32;3]) -> bool {
+            !(180f32..=260f32).contains(&hsl[0])
+        }
+    }
+
+    let filters = FilterSet::new().default().add(NotBlue).build();
+    assert_eq!(filters.len(), 2);
+
+    let is_allowed = |color: ColorInt| {
+        let mut hsl = [0f32;3];
+        ColorUtils::color_to_hsl(color, &mut hsl);
+        filters.iter().all(|filter| filter.is_allowed(*color, hsl))
+    };
+    assert!(is_allowed(Color::rgb(40, 160, 60)));
+    assert!(!is_allowed(Color::rgb(40, 60, 160)));
+    assert!(!is_allowed(Color::BLACK));
+    assert!(!is_allowed(Color::WHITE));
+}
+
+#[test]
+fn test_dominant_swatch_tiebreak() {
+    let a = Swatch::new(Color::rgb(10, 200, 30), 50);
+    let b = Swatch::new(Color::rgb(200, 10, 30), 50);
+    let c = Swatch::new(Color::rgb(0, 0, 255), 20);
+
+    let forward = Palette::new(vec![a.clone(), b.clone(), c.clone()], vec![]);
+    let backward = Palette::new(vec![c, b, a], vec![]);
+    assert_eq!(forward.m_dominant_swatch.get_rgb(), Color::rgb(10, 200, 30));
+    assert_eq!(backward.m_dominant_swatch.get_rgb(), Color::rgb(10, 200, 30));
+}
+
+#[test]
+fn test_background_contrast_target() {
+    let target = Target::for_background(Color::WHITE);
+    let palette = Palette::new(vec![
+        Swatch::new(Color::rgb(250, 240, 170), 500),
+        Swatch::new(Color::rgb(120, 150, 190), 300),
+        Swatch::new(Color::rgb(20, 30, 80), 10),
+    ], vec![target.clone()]);
+
+    let swatch = palette.get_swatch_for_target(&target).unwrap();
+    assert_eq!(swatch.get_rgb(), Color::rgb(20, 30, 80));
+    assert!(ColorUtils::calculate_contrast(swatch.get_rgb(), Color::WHITE) > 4.5);
+}
+
+#[test]
+fn test_color_with_fallback() {
+    use TargetKind::*;
+
+    let targets = vec![
+        Target::new(LightVibrant), Target::new(Vibrant), Target::new(DarkVibrant),
+        Target::new(LightMuted), Target::new(Muted), Target::new(DarkMuted),
+    ];
+    let pink = Color::rgb(255, 150, 150);
+    let dark = Color::rgb(60, 50, 50);
+    let palette = Palette::new(vec![Swatch::new(pink, 10), Swatch::new(dark, 90)], targets);
+
+    assert!(palette.get_swatch_for_target(&Target::new(Vibrant)).is_none());
+    assert_eq!(palette.get_color_with_fallback(&[Vibrant, LightVibrant], Color::TRANSPARENT), pink);
+    assert_eq!(palette.get_color_with_fallback(&[Vibrant], Color::TRANSPARENT), dark);
+
+    let empty = Palette::new(vec![], vec![Target::new(Vibrant)]);
+    assert_eq!(empty.get_color_with_fallback(&[Vibrant], Color::GRAY), Color::GRAY);
+}
+
+#[test]
+fn test_max_input_pixels() {
+    let red = Color::rgb(200, 40, 40);
+
+    let result = PaletteBuilder::new(Image::solid(20, 20, red)).max_input_pixels(100).generate();
+    assert_eq!(result.err(), Some(PaletteError::TooManyPixels { pixels: 400, max: 100 }));
+
+    let palette = PaletteBuilder::new(Image::solid(20, 20, red)).max_input_pixels(400).generate().unwrap();
+    assert_eq!(palette.get_swatches().len(), 1);
+    assert_eq!(palette.get_swatches()[0].get_rgb(), red);
+    assert_eq!(palette.get_swatches()[0].get_population(), 400);
+}
+
+#[cfg(feature = "gif")]
+#[test]
+fn test_palette_from_gif_frames() {
+    let red = Color::rgb(200, 0, 0);
+    let blue = Color::rgb(0, 0, 200);
+
+    let mut gif = vec![];
+    {
+        let mut encoder = gif::Encoder::new(&mut gif, 4, 4, &[]).unwrap();
+        for color in [red, blue] {
+            let mut rgba = [color.red(), color.green(), color.blue(), 255].repeat(16);
+            encoder.write_frame(&gif::Frame::from_rgba(4, 4, &mut rgba)).unwrap();
+        }
+    }
+
+    let mut frames = Image::frames_from_gif(gif.as_slice()).unwrap().into_iter();
+    assert_eq!(frames.len(), 2);
+
+    let palette = PaletteBuilder::new(frames.next().unwrap())
+        .add_image(frames.next().unwrap())
+        .generate()
+        .unwrap();
+    let colors: Vec<ColorInt> = palette.get_swatches().iter().map(Swatch::get_rgb).collect();
+    assert!(colors.contains(&red));
+    assert!(colors.contains(&blue));
+}
+
+#[test]
+fn test_registered_target_by_name() {
+    use crate::graphics::TargetBuilder;
+
+    let brand = TargetBuilder::new(TargetKind::DarkVibrant)
+        .set_minimum_lightness(0.2)
+        .set_target_lightness(0.3)
+        .set_maximum_lightness(0.4)
+        .build();
+    Target::register("brand", brand.clone());
+
+    let navy = Color::rgb(20, 30, 120);
+    let palette = Palette::new(vec![
+        Swatch::new(Color::rgb(255, 180, 180), 100),
+        Swatch::new(navy, 10),
+    ], vec![Target::named("brand").unwrap(), Target::named("light_vibrant").unwrap()]);
+
+    assert_eq!(palette.get_swatch_for_name("brand").map(Swatch::get_rgb), Some(navy));
+    assert_eq!(palette.get_swatch_for_target(&brand).map(Swatch::get_rgb), Some(navy));
+    assert!(palette.get_swatch_for_name("unregistered").is_none());
+}
+
+#[test]
+fn test_swatch_near_hue() {
+    let red = Color::rgb(220, 30, 30);
+    let blue = Color::rgb(30, 60, 220);
+    let teal = Color::rgb(30, 200, 180);
+    let palette = Palette::new(vec![
+        Swatch::new(red, 500),
+        Swatch::new(blue, 40),
+        Swatch::new(teal, 10),
+    ], vec![]);
+
+    assert_eq!(palette.swatch_near_hue(230f32, 20f32).map(Swatch::get_rgb), Some(blue));
+    assert_eq!(palette.swatch_near_hue(350f32, 15f32).map(Swatch::get_rgb), Some(red));
+    assert!(palette.swatch_near_hue(100f32, 20f32).is_none());
+}
+
+#[test]
+fn test_palette_diff() {
+    use TargetKind::*;
+
+    let targets = || vec![Target::new(Vibrant), Target::new(DarkVibrant), Target::new(LightMuted)];
+    let palette = Palette::new(vec![
+        Swatch::new(Color::rgb(220, 40, 40), 100),
+        Swatch::new(Color::rgb(110, 20, 20), 50),
+    ], targets());
+    let shifted = Palette::new(vec![
+        Swatch::new(Color::rgb(220, 90, 40), 100),
+        Swatch::new(Color::rgb(110, 20, 20), 50),
+    ], targets());
+
+    let same = palette.diff(&palette);
+    assert_eq!(same.iter().map(|&(kind, _)| kind).collect::<Vec<_>>(), vec![Vibrant, DarkVibrant]);
+    assert!(same.iter().all(|&(_, distance)| distance == 0f64));
+
+    let drift = palette.diff(&shifted);
+    assert_eq!(drift[0].0, Vibrant);
+    assert!(drift[0].1 > 5f64);
+    assert_eq!(drift[1], (DarkVibrant, 0f64));
+}
+
+#[test]
+fn test_palette_view_borrows_swatches() {
+    let palette = Palette::new(vec![
+        Swatch::new(Color::rgb(220, 40, 40), 100),
+        Swatch::new(Color::rgb(110, 20, 20), 50),
+    ], vec![Target::new(TargetKind::Vibrant), Target::new(TargetKind::DarkVibrant)]);
+
+    let view = palette.view();
+    let first = view.get(TargetKind::Vibrant).unwrap();
+    let second = view.get(TargetKind::Vibrant).unwrap();
+    assert!(std::ptr::eq(first, second));
+    assert!(std::ptr::eq(first, &palette.get_swatches()[0]));
+    assert!(std::ptr::eq(view.get(TargetKind::DarkVibrant).unwrap(), &palette.get_swatches()[1]));
+    assert!(view.get(TargetKind::Muted).is_none());
+    assert!(std::ptr::eq(view.dominant_swatch().unwrap(), first));
+}
+
+#[test]
+fn test_text_contrast_thresholds() {
+    let image = || Image::solid(4, 4, Color::rgb(40, 40, 136));
+
+    let mut default = PaletteBuilder::new(image()).generate().unwrap().get_swatches()[0].clone();
+    let mut strict = PaletteBuilder::new(image()).text_contrast(4.5, 7.0).generate().unwrap().get_swatches()[0].clone();
+
+    let background = default.get_rgb();
+    let default_body = default.get_body_text_color();
+    let strict_body = strict.get_body_text_color();
+    assert_ne!(default_body, strict_body);
+
+    let contrast = |text| ColorUtils::calculate_contrast(text, background);
+    assert!(contrast(default_body) >= 4.5);
+    assert!(contrast(strict_body) >= 7.0);
+    assert!(contrast(strict_body) > contrast(default_body));
+}
+
+#[test]
+fn test_palette_exports() {
+    let palette = Palette::new(vec![
+        Swatch::new(Color::rgb(220, 40, 40), 100),
+        Swatch::new(Color::rgb(8, 16, 255), 50),
+    ], vec![]);
+
+    let gpl = palette.to_gpl("Sunset");
+    let mut lines = gpl.lines();
+    assert_eq!(lines.next(), Some("GIMP Palette"));
+    assert_eq!(lines.next(), Some("Name: Sunset"));
+    assert_eq!(lines.next(), Some("Columns: 0"));
+    assert_eq!(lines.next(), Some("#"));
+    let colors: Vec<Vec<u8>> = lines
+        .map(|line| line.split_whitespace().take(3).map(|c| c.parse().unwrap()).collect())
+        .collect();
+    assert_eq!(colors, vec![vec![220, 40, 40], vec![8, 16, 255]]);
+
+    let ase = palette.to_ase();
+    assert_eq!(&ase[0..4], b"ASEF");
+    assert_eq!(ase[4..8], [0, 1, 0, 0]);
+    assert_eq!(u32::from_be_bytes(ase[8..12].try_into().unwrap()), 2);
+    assert_eq!(ase[12..14], [0, 1]);
+    let block_len = u32::from_be_bytes(ase[14..18].try_into().unwrap()) as usize;
+    assert_eq!(ase.len(), 12 + 2 * (6 + block_len));
+}
+
+#[test]
+fn test_swatch_conversions() {
+    let swatch = Swatch::new(Color::rgb(12, 34, 56), 10);
+    let rgb = swatch.get_rgb();
+    assert_eq!(ColorInt::from(&swatch), rgb);
+    let (r, g, b): (u8, u8, u8) = (&swatch).into();
+    assert_eq!((r, g, b), (Color::red(rgb), Color::green(rgb), Color::blue(rgb)));
+}
+
+#[test]
+fn test_without_default_filter() {
+    let black = Color::rgb(0, 0, 0);
+
+    let palette = PaletteBuilder::new(Image::solid(8, 8, black)).generate().unwrap();
+    assert!(palette.get_swatches().iter().all(|swatch| swatch.get_rgb() != black));
+
+    let palette = PaletteBuilder::new(Image::solid(8, 8, black))
+        .without_default_filter()
+        .generate()
+        .unwrap();
+    assert_eq!(palette.get_swatches().len(), 1);
+    assert_eq!(palette.get_swatches()[0].get_rgb(), black);
+}
+
+#[test]
+fn test_exclude_dominant_from_targets() {
+    let dominant = Color::rgb(200, 24, 24);
+    let other = Color::rgb(24, 24, 200);
+    let image = || {
+        let mut image = Image::solid(10, 10, dominant);
+        for pixel in image.chunks_exact_mut(4).take(20) {
+            pixel.copy_from_slice(&[24, 24, 200, 255]);
+        }
+        image
+    };
+    let vibrant = Target::new(TargetKind::Vibrant);
+
+    let palette = PaletteBuilder::new(image()).generate().unwrap();
+    assert_eq!(palette.get_swatch_for_target(&vibrant).unwrap().get_rgb(), dominant);
+
+    let palette = PaletteBuilder::new(image()).exclude_dominant_from_targets(true).generate().unwrap();
+    assert_eq!(palette.view().dominant_swatch().unwrap().get_rgb(), dominant);
+    assert_eq!(palette.get_swatch_for_target(&vibrant).unwrap().get_rgb(), other);
+}
+
+#[test]
+fn test_palette_cache_hits() {
+    use super::color_cut_quantizer::QUANTIZE_COUNT;
+
+    let quantizations = || QUANTIZE_COUNT.with(|count| count.get());
+    let red = || Image::solid(8, 8, Color::rgb(200, 40, 40));
+    let green = || Image::solid(8, 8, Color::rgb(40, 200, 40));
+    let mut cache = PaletteCache::new(1);
+
+    let before = quantizations();
+    let rgb = cache.get_or_generate(PaletteBuilder::new(red())).unwrap().get_swatches()[0].get_rgb();
+    assert_eq!(quantizations(), before + 1);
+    let again = cache.get_or_generate(PaletteBuilder::new(red())).unwrap().get_swatches()[0].get_rgb();
+    assert_eq!(quantizations(), before + 1);
+    assert_eq!(rgb, again);
+
+    // Different options or pixels miss, and evict the least recently used palette.
+    cache.get_or_generate(PaletteBuilder::new(red()).without_default_filter()).unwrap();
+    assert_eq!(quantizations(), before + 2);
+    cache.get_or_generate(PaletteBuilder::new(green())).unwrap();
+    cache.get_or_generate(PaletteBuilder::new(red())).unwrap();
+    assert_eq!(quantizations(), before + 4);
+    assert_eq!(cache.len(), 1);
+
+    // Builders with custom filters are never cached
+    struct NotRed;
+
+    impl Filter for NotRed {
+        fn is_allowed(&self, _rgb: i32, hsl: [f32;3]) -> bool {
+            !(hsl[0] < 20f32 || hsl[0] > 340f32)
+        }
+    }
+
+    assert!(cache.get_or_generate(PaletteBuilder::new(red()).add_filter(Box::new(NotRed))).unwrap().get_swatches().is_empty());
+    assert_eq!(quantizations(), before + 5);
+    assert_eq!(cache.get_or_generate(PaletteBuilder::new(red())).unwrap().get_swatches()[0].get_rgb(), rgb);
+    assert_eq!(quantizations(), before + 5);
+}
+
+#[test]
+fn test_swatches_by_role() {
+    let mut image = Image::solid(10, 10, Color::rgb(200, 24, 24));
+    let others = [[24, 24, 200, 255], [240, 200, 210, 255], [60, 70, 50, 255]];
+    for (i, pixel) in image.chunks_exact_mut(4).enumerate().take(60) {
+        pixel.copy_from_slice(&others[i % 3]);
+    }
+    let palette = PaletteBuilder::new(image).generate().unwrap();
+    let roles = palette.swatches_by_role();
+
+    let rgb = |swatch: Option<&Swatch>| swatch.map(Swatch::get_rgb);
+    for kind in TargetKind::ALL {
+        assert_eq!(rgb(roles.get(kind)), rgb(palette.get_swatch_for_target(&Target::new(kind))));
+    }
+    assert!(roles.vibrant.is_some());
+    assert_eq!(rgb(roles.dominant), rgb(palette.view().dominant_swatch()));
+
+    let empty = Palette::new(vec![], vec![]);
+    let roles = empty.swatches_by_role();
+    assert!(roles.dominant.is_none() && roles.vibrant.is_none());
+}
+
+#[test]
+fn test_builder_setters() {
+    let (red, blue) = (Color::rgb(200, 16, 16), Color::rgb(16, 16, 200));
+    let mut image = Image::solid(200, 100, blue);
+    for pixel in image.chunks_exact_mut(4).take(200 * 20) {
+        pixel.copy_from_slice(&[200, 16, 16, 255]);
+    }
+    let colors = |builder: PaletteBuilder| -> Vec<ColorInt> {
+        builder.generate().unwrap().get_swatches().iter().map(Swatch::get_rgb).collect()
+    };
+
+    let banner = colors(PaletteBuilder::new(image.clone()).set_region(0, 0, 200, 20));
+    assert_eq!(banner, vec![red]);
+    let whole = colors(PaletteBuilder::new(image.clone()).set_region(0, 0, 200, 20).clear_region());
+    assert!(whole.contains(&red) && whole.contains(&blue));
+
+    let resized = PaletteBuilder::new(image.clone()).resize_bitmap_area(50).generate().unwrap();
+    assert!(resized.get_swatches().iter().map(Swatch::get_population).sum::<i32>() <= 50);
+    let resized = PaletteBuilder::new(image.clone()).resize_image_max_dimension(10).generate().unwrap();
+    assert_eq!(resized.get_swatches().iter().map(Swatch::get_population).sum::<i32>(), 50);
+
+    struct NotRed;
+
+    impl Filter for NotRed {
+        fn is_allowed(&self, _rgb: i32, hsl: [f32;3]) -> bool {
+            !(hsl[0] < 20f32 || hsl[0] > 340f32)
+        }
+    }
+
+    assert_eq!(colors(PaletteBuilder::new(image.clone()).add_filter(Box::new(NotRed))), vec![blue]);
+    assert_eq!(colors(PaletteBuilder::new(image.clone()).filters(FilterSet::new().add(NotRed))), vec![blue]);
+    let unfiltered = PaletteBuilder::new(image.clone()).add_filter(Box::new(NotRed)).clear_filters();
+    assert_eq!(colors(unfiltered.maximum_color_count(1)).len(), 1);
+
+    let builder = PaletteBuilder::new(image).add_target(Target::new(TargetKind::Vibrant));
+    assert_eq!(builder.m_targets.len(), 6);
+}
+
+#[test]
+fn test_target_selection() {
+    use crate::graphics::TargetBuilder;
+
+    let vivid = Swatch::new(Color::rgb(240, 16, 16), 10);
+    let dull = Swatch::new(Color::rgb(136, 112, 112), 100);
+    let saturated = |target: f32| TargetBuilder::default()
+        .set_target_saturation(target)
+        .set_saturation_weight(1f32)
+        .set_lightness_weight(0f32)
+        .set_population_weight(0f32);
+    let rgb = |palette: &Palette, target: &Target| palette.get_swatch_for_target(target).map(Swatch::get_rgb);
+
+    // The first target takes the vivid color, so the exclusive second one gets the dull color
+    let (first, second) = (saturated(1f32).build(), saturated(0.9).build());
+    let palette = Palette::new(vec![dull.clone(), vivid.clone()], vec![first.clone(), second.clone()]);
+    assert_eq!(rgb(&palette, &first), Some(vivid.get_rgb()));
+    assert_eq!(rgb(&palette, &second), Some(dull.get_rgb()));
+
+    let shared = saturated(1f32).set_exclusive(false).build();
+    let palette = Palette::new(vec![dull.clone(), vivid.clone()], vec![shared.clone(), second.clone()]);
+    assert_eq!(rgb(&palette, &shared), Some(vivid.get_rgb()));
+    assert_eq!(rgb(&palette, &second), Some(vivid.get_rgb()));
+
+    // Swatches outside of the saturation range are never scored
+    let capped = saturated(1f32).set_maximum_saturation(0.5).build();
+    let populous = TargetBuilder::default().set_population_weight(1f32).set_saturation_weight(0f32).build();
+    let out_of_range = saturated(1f32).set_minimum_lightness(0.9).build();
+    let palette = Palette::new(vec![dull.clone(), vivid.clone()], vec![capped.clone(), populous.clone(), out_of_range.clone()]);
+    assert_eq!(rgb(&palette, &capped), Some(dull.get_rgb()));
+    // The populous dull color is already used, leaving only the vivid one
+    assert_eq!(rgb(&palette, &populous), Some(vivid.get_rgb()));
+    assert_eq!(rgb(&palette, &out_of_range), None);
+}
@@ -1,4 +1,13 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+
+use super::{ColorInt, ColorUtils};
+
 /// Kind of target to Build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TargetKind {
     /// A target which has the characteristics of a vibrant color which is light in luminance.
     LightVibrant,
@@ -14,16 +23,63 @@ pub enum TargetKind {
     DarkMuted
 }
 
+impl TargetKind {
+    /// Every kind, in the order of the default targets of a
+    /// [PaletteBuilder](super::PaletteBuilder).
+    pub const ALL: [TargetKind; 6] = [
+        TargetKind::LightVibrant, TargetKind::Vibrant, TargetKind::DarkVibrant,
+        TargetKind::LightMuted, TargetKind::Muted, TargetKind::DarkMuted,
+    ];
+}
+
+/// Error returned when a string does not name a [TargetKind].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownTargetKind(pub String);
+
+impl fmt::Display for UnknownTargetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown target kind `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownTargetKind {}
+
+impl FromStr for TargetKind {
+    type Err = UnknownTargetKind;
+
+    /// Parses a target name such as `"vibrant"` or `"dark_muted"`. Case is ignored and `-` may
+    /// be used in place of `_`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use TargetKind::*;
+        match s.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "light_vibrant" => Ok(LightVibrant),
+            "vibrant" => Ok(Vibrant),
+            "dark_vibrant" => Ok(DarkVibrant),
+            "light_muted" => Ok(LightMuted),
+            "muted" => Ok(Muted),
+            "dark_muted" => Ok(DarkMuted),
+            _ => Err(UnknownTargetKind(s.to_string()))
+        }
+    }
+}
+
 /// A struct which allows custom selection of colors in a [Palette](super::Palette)'s generation. Instances
 /// can be created via the [TargetBuilder] class.
 ///
 /// To use the target, use the [add_target](super::PaletteBuilder::add_target) API when building a
 /// Palette.
+///
+/// Two targets are equal when all of their saturation, lightness and weight values, their
+/// exclusivity and their contrast background are equal, so a target built with the same
+/// settings can be used to look up a selected swatch. The float values are compared and hashed
+/// by their bit patterns, so `0.0` and `-0.0` are different values while `NaN` equals itself.
+#[derive(Debug, Clone)]
 pub struct Target {
     m_saturation_targets: [f32;3],
     m_lightness_targets: [f32;3],
     m_weights: [f32;3],
     m_is_exclusive: bool,
+    m_contrast_background: Option<ColorInt>,
 }
 
 impl Default for Target {
@@ -32,7 +88,8 @@ impl Default for Target {
             m_saturation_targets: [0.0;3],
             m_lightness_targets: [0.0;3],
             m_weights: [0.0;3],
-            m_is_exclusive: true
+            m_is_exclusive: true,
+            m_contrast_background: None
         };
         target.set_target_default_values();
         target.set_default_weights();
@@ -235,7 +292,68 @@ impl Target {
         self.m_is_exclusive
     }
 
+    /// Creates a target which selects the color with the highest contrast against `background`,
+    /// regardless of its saturation and lightness. Useful for accessibility-first palettes.
+    ///
+    /// `background` is made opaque, as contrast is only defined against opaque colors.
+    pub fn for_background(background: ColorInt) -> Target {
+        Target {
+            m_weights: [0.0;3],
+            m_contrast_background: Some(ColorUtils::set_alpha_component(background, 255)),
+            ..Target::default()
+        }
+    }
+
+    /// Returns the background this target maximizes contrast against, if any.
+    ///
+    /// See also [for_background][Target::for_background]
+    pub fn get_contrast_background(&self) -> Option<ColorInt> {
+        self.m_contrast_background
+    }
+
+    /// Register `target` under `name` so it can be looked up with [Target::named], e.g. from a
+    /// manifest. Registering a name again replaces the previous target.
+    pub fn register(name: &str, target: Target) {
+        registry().lock().unwrap().insert(name.to_string(), target);
+    }
+
+    /// Returns the target registered under `name`, or the preset of the [TargetKind] with that
+    /// name. A registered name shadows the preset of the same name.
+    pub fn named(name: &str) -> Option<Target> {
+        let registered = registry().lock().unwrap().get(name).cloned();
+        registered.or_else(|| name.parse().ok().map(Target::new))
+    }
+
+    /// The values which make up the identity of this target.
+    fn key(&self) -> ([u32;3], [u32;3], [u32;3], bool, Option<ColorInt>) {
+        (
+            self.m_saturation_targets.map(f32::to_bits),
+            self.m_lightness_targets.map(f32::to_bits),
+            self.m_weights.map(f32::to_bits),
+            self.m_is_exclusive,
+            self.m_contrast_background
+        )
+    }
+}
+
+impl PartialEq for Target {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Target {}
+
+impl Hash for Target {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
 
+/// Targets added with [Target::register].
+fn registry() -> &'static Mutex<HashMap<String, Target>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, Target>>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
 }
 
 /// TargetBuilder struct for generating custom [Target] instances.
@@ -350,3 +468,27 @@ impl TargetBuilder {
     }
 }
 
+#[test]
+fn test_target_kind_from_str() {
+    assert_eq!("vibrant".parse(), Ok(TargetKind::Vibrant));
+    assert_eq!("Light-Muted".parse(), Ok(TargetKind::LightMuted));
+    assert_eq!(" dark_vibrant ".parse(), Ok(TargetKind::DarkVibrant));
+    assert!("neon".parse::<TargetKind>().is_err());
+}
+
+#[test]
+fn test_target_identity() {
+    use std::collections::HashMap;
+
+    let mut selected = HashMap::new();
+    selected.insert(Target::new(TargetKind::Vibrant), 1);
+    selected.insert(TargetBuilder::new(TargetKind::Muted).set_exclusive(false).build(), 2);
+
+    assert_eq!(selected.get(&TargetBuilder::new(TargetKind::Vibrant).build()), Some(&1));
+    assert_eq!(selected.get(&TargetBuilder::new(TargetKind::Muted).set_exclusive(false).build()), Some(&2));
+    assert_eq!(selected.get(&Target::new(TargetKind::Muted)), None);
+    assert_ne!(
+        TargetBuilder::new(TargetKind::Vibrant).set_population_weight(0.0).build(),
+        TargetBuilder::new(TargetKind::Vibrant).set_population_weight(-0.0).build()
+    );
+}
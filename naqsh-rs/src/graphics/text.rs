@@ -0,0 +1,238 @@
+use swash::scale::{Render, ScaleContext, Source};
+use swash::shape::{Direction, ShapeContext};
+use swash::text::{BidiClass, Codepoint, Script};
+use swash::zeno::{Format, Vector};
+
+use crate::font::Font;
+use crate::image::Image;
+use crate::object::{BoundingBox, Rect};
+
+use super::{Color, ColorInt};
+
+/// Direction in which a line of [Text] is laid out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextDirection {
+    /// Runs are placed from left to right.
+    #[default]
+    Ltr,
+    /// Runs are placed from right to left, as for Arabic or Hebrew.
+    Rtl,
+    /// The direction of the first strongly directional character, or [TextDirection::Ltr] if
+    /// there is none.
+    Auto,
+}
+
+/// A single line of text drawn onto a [Canvas](super::Canvas) with [Draw](super::Draw).
+///
+/// The text is filled with the fill color of the canvas and its origin is the top-left corner
+/// of the line, so the baseline lies `ascent` pixels below it.
+///
+/// The text is split into runs of a single script, each shaped on its own so that Arabic
+/// letters join and take their initial, medial, final or isolated forms. Right-to-left runs are
+/// reversed, and the runs are ordered according to the [TextDirection] of the line. The line
+/// always starts at the origin, whatever its direction.
+///
+/// <pre>
+/// let canvas = canvas.with_fill(Color::BLACK).draw(Text::new("naqsh", &font, 24.0), (10, 10));
+/// </pre>
+pub struct Text<'a> {
+    content: &'a str,
+    font: &'a Font,
+    size: f32,
+    direction: TextDirection,
+}
+
+/// A shaped glyph, positioned relative to the start of the baseline.
+struct PlacedGlyph {
+    id: u16,
+    x: f32,
+    y: f32,
+}
+
+impl<'a> Text<'a> {
+    /// Creates `content` set in `font` at `size` pixels per em.
+    pub fn new(content: &'a str, font: &'a Font, size: f32) -> Self {
+        Self { content, font, size, direction: TextDirection::Ltr }
+    }
+
+    /// Sets the direction of the line, defaults to [TextDirection::Ltr].
+    pub fn with_direction(mut self, direction: TextDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Returns whether the line is laid out from right to left.
+    fn is_rtl(&self) -> bool {
+        match self.direction {
+            TextDirection::Ltr => false,
+            TextDirection::Rtl => true,
+            TextDirection::Auto => self.content.chars().find_map(strong_direction).unwrap_or(false),
+        }
+    }
+
+    /// Shapes the text into glyphs in visual order. Returns the glyphs and the total advance
+    /// of the line.
+    fn shape(&self) -> (Vec<PlacedGlyph>, f32) {
+        let mut context = ShapeContext::new();
+        let mut runs = vec![];
+        for (script, rtl, run) in script_runs(self.content) {
+            let direction = if rtl { Direction::RightToLeft } else { Direction::LeftToRight };
+            let mut shaper = context.builder(self.font.as_ref())
+                .size(self.size)
+                .script(script)
+                .direction(direction)
+                .build();
+            shaper.add_str(run);
+
+            // Clusters are produced in logical order, right-to-left runs are reversed by
+            // cluster so that marks stay in order within a cluster
+            let mut clusters = vec![];
+            shaper.shape_with(|cluster| {
+                let glyphs: Vec<_> = cluster.glyphs.iter().map(|glyph| (glyph.id, glyph.x, glyph.y, glyph.advance)).collect();
+                clusters.push(glyphs);
+            });
+            if rtl {
+                clusters.reverse();
+            }
+            runs.push(clusters);
+        }
+        if self.is_rtl() {
+            runs.reverse();
+        }
+
+        let mut glyphs = vec![];
+        let mut pen = 0f32;
+        for (id, x, y, advance) in runs.into_iter().flatten().flatten() {
+            glyphs.push(PlacedGlyph { id, x: pen + x, y });
+            pen += advance;
+        }
+        (glyphs, pen)
+    }
+
+    /// Rasterizes the text in `color`. Returns the image and the position of its top-left
+    /// corner relative to the origin of the text, since glyphs may overhang the line box.
+    pub(crate) fn render(&self, color: ColorInt) -> (Image, (i32, i32)) {
+        let font = self.font.as_ref();
+        let metrics = font.metrics(&[]).scale(self.size);
+        let baseline = metrics.ascent.ceil() as i32;
+        let line_height = baseline + metrics.descent.abs().ceil() as i32;
+        let (glyphs, advance) = self.shape();
+
+        let mut context = ScaleContext::new();
+        let mut scaler = context.builder(font).size(self.size).hint(true).build();
+        let mut bounds = BoundingBox::new();
+        bounds.include(&Rect::new(0, 0, advance.ceil() as i32, line_height));
+
+        // Coverage masks of the glyphs with the position of their top-left corner
+        let mut masks = vec![];
+        for glyph in glyphs {
+            let mask = Render::new(&[Source::Outline])
+                .format(Format::Alpha)
+                .offset(Vector::new(glyph.x.fract(), 0f32))
+                .render(&mut scaler, glyph.id);
+            let Some(mask) = mask else {
+                continue;
+            };
+            let placement = mask.placement;
+            let left = glyph.x.floor() as i32 + placement.left;
+            let top = baseline - glyph.y.round() as i32 - placement.top;
+            bounds.include(&Rect::new(left, top, left + placement.width as i32, top + placement.height as i32));
+            masks.push((left, top, mask));
+        }
+
+        let bounds = bounds.finish().unwrap_or_default();
+        let (width, height) = (bounds.width() as usize, bounds.height() as usize);
+        let mut coverage = vec![0u8; width * height];
+        for (left, top, mask) in masks {
+            let mask_width = mask.placement.width as usize;
+            for (row, line) in mask.data.chunks_exact(mask_width.max(1)).enumerate() {
+                let y = (top - bounds.top) as usize + row;
+                let start = y * width + (left - bounds.left) as usize;
+                // Overlapping glyphs, such as joined letters, keep the strongest coverage
+                for (dst, &src) in coverage[start..start + mask_width].iter_mut().zip(line) {
+                    *dst = (*dst).max(src);
+                }
+            }
+        }
+
+        let buf = coverage.iter()
+            .flat_map(|&c| {
+                let alpha = (c as u32 * Color::alpha(color) as u32 / 255) as u8;
+                [Color::red(color), Color::green(color), Color::blue(color), alpha]
+            })
+            .collect();
+        (Image::from((width, height, buf)), (bounds.left, bounds.top))
+    }
+}
+
+/// Returns `Some(true)` for strongly right-to-left characters, `Some(false)` for strongly
+/// left-to-right ones and `None` for the rest.
+fn strong_direction(ch: char) -> Option<bool> {
+    match ch.bidi_class() {
+        BidiClass::R | BidiClass::AL => Some(true),
+        BidiClass::L => Some(false),
+        _ => None,
+    }
+}
+
+/// Splits `text` into runs of a single script with their direction. Characters shared between
+/// scripts, like spaces and punctuation, stay in the current run.
+fn script_runs(text: &str) -> Vec<(Script, bool, &str)> {
+    let mut runs = vec![];
+    let mut start = 0;
+    let mut current: Option<(Script, bool)> = None;
+    for (index, ch) in text.char_indices() {
+        let script = ch.script();
+        if matches!(script, Script::Common | Script::Inherited | Script::Unknown) {
+            continue;
+        }
+        let rtl = strong_direction(ch).unwrap_or(false);
+        match current {
+            Some(run) if run == (script, rtl) => {}
+            Some((script, rtl)) => {
+                runs.push((script, rtl, &text[start..index]));
+                start = index;
+            }
+            None => {}
+        }
+        current = Some((script, rtl));
+    }
+    if start < text.len() {
+        let (script, rtl) = current.unwrap_or((Script::Latin, false));
+        runs.push((script, rtl, &text[start..]));
+    }
+    runs
+}
+
+#[test]
+fn test_script_runs() {
+    let runs = script_runs("abc سلام, def");
+    assert_eq!(runs, vec![(Script::Latin, false, "abc "), (Script::Arabic, true, "سلام, "), (Script::Latin, false, "def")]);
+    assert_eq!(script_runs("12 !"), vec![(Script::Latin, false, "12 !")]);
+    assert!(script_runs("").is_empty());
+}
+
+#[test]
+fn test_arabic_shaping() {
+    let font = Font::from_bytes(crate::font::TEST_FONT.to_vec(), 0).unwrap();
+    let ids = |text: &Text| text.shape().0.iter().map(|glyph| glyph.id).collect::<Vec<_>>();
+    let charmap = font.charmap();
+
+    // Joined letters take their initial and final forms instead of the isolated one
+    let shaped = ids(&Text::new("بب", &font, 20.0));
+    assert_eq!(shaped.len(), 2);
+    assert_ne!(shaped[0], shaped[1]);
+    assert!(shaped.iter().all(|&id| id != charmap.map('ب')));
+
+    // Latin text keeps its glyphs and order in every direction
+    let latin = ids(&Text::new("ab", &font, 20.0));
+    assert_eq!(latin, vec![charmap.map('a'), charmap.map('b')]);
+    assert_eq!(ids(&Text::new("ab", &font, 20.0).with_direction(TextDirection::Rtl)), latin);
+
+    // Auto picks the direction of the first strong character and orders the runs accordingly
+    let mixed = |direction| ids(&Text::new("a ب", &font, 20.0).with_direction(direction));
+    assert_eq!(mixed(TextDirection::Ltr)[0], charmap.map('a'));
+    assert_eq!(mixed(TextDirection::Rtl)[0], *mixed(TextDirection::Ltr).last().unwrap());
+    assert_eq!(mixed(TextDirection::Auto), mixed(TextDirection::Ltr));
+    assert!(Text::new("ب a", &font, 20.0).with_direction(TextDirection::Auto).is_rtl());
+}
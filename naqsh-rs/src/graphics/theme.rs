@@ -0,0 +1,75 @@
+use super::{Color, ColorUtils, ColorInt, Palette, Swatch, Target, TargetKind};
+
+/// Semantic colors for an app, derived from the swatches of a [Palette].
+///
+/// Every `on_*` color is the body text color of the matching role, composited over it so all
+/// colors are opaque.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Main brand color, taken from the vibrant swatch.
+    pub primary: ColorInt,
+    /// Color for text and icons drawn over `primary`.
+    pub on_primary: ColorInt,
+    /// Background color, taken from the light muted swatch.
+    pub surface: ColorInt,
+    /// Color for text and icons drawn over `surface`.
+    pub on_surface: ColorInt,
+    /// Highlight color, taken from the light vibrant swatch.
+    pub accent: ColorInt,
+}
+
+impl Theme {
+    const FALLBACK_PRIMARY: ColorInt = Color::DKGRAY;
+    const FALLBACK_SURFACE: ColorInt = Color::WHITE;
+
+    /// Derives a theme from `palette`. Each role falls back to similar targets, then to the
+    /// dominant swatch, and finally to a neutral color if the palette is empty.
+    pub fn from_palette(palette: &Palette) -> Theme {
+        use TargetKind::*;
+
+        let mut primary = Self::role(palette, &[Vibrant, DarkVibrant, Muted], Self::FALLBACK_PRIMARY);
+        let mut surface = Self::role(palette, &[LightMuted, Muted, LightVibrant], Self::FALLBACK_SURFACE);
+        let accent = Self::role(palette, &[LightVibrant, Vibrant, DarkVibrant], primary.get_rgb());
+
+        Theme {
+            primary: primary.get_rgb(),
+            on_primary: Self::on(&mut primary),
+            surface: surface.get_rgb(),
+            on_surface: Self::on(&mut surface),
+            accent: accent.get_rgb(),
+        }
+    }
+
+    fn role(palette: &Palette, kinds: &[TargetKind], fallback: ColorInt) -> Swatch {
+        let view = palette.view();
+        kinds.iter()
+            .find_map(|&kind| view.get_swatch_for_target(&Target::new(kind)))
+            .or_else(|| view.dominant_swatch())
+            .cloned()
+            .unwrap_or_else(|| Swatch::new(fallback, 0))
+    }
+
+    fn on(swatch: &mut Swatch) -> ColorInt {
+        ColorUtils::composite_colors(swatch.get_body_text_color(), swatch.get_rgb()).into()
+    }
+}
+
+#[test]
+fn test_on_primary_contrast() {
+    use super::PaletteBuilder;
+    use crate::image::Image;
+
+    let palette = PaletteBuilder::new(Image::checkerboard(8, 8, 2, Color::rgb(224, 64, 40), Color::rgb(232, 224, 208)))
+        .generate()
+        .unwrap();
+    let theme = Theme::from_palette(&palette);
+    assert_eq!(theme.primary, Color::rgb(224, 64, 40));
+    assert_eq!(theme.surface, Color::rgb(232, 224, 208));
+    assert!(ColorUtils::calculate_contrast(theme.on_primary, theme.primary) >= 4.5);
+    assert!(ColorUtils::calculate_contrast(theme.on_surface, theme.surface) >= 4.5);
+    assert_eq!(theme.on_primary.alpha(), 255);
+
+    let empty = Theme::from_palette(&Palette::new(vec![], vec![]));
+    assert_eq!(empty.primary, Color::DKGRAY);
+    assert!(ColorUtils::calculate_contrast(empty.on_primary, empty.primary) >= 4.5);
+}
@@ -1,8 +1,18 @@
 #![allow(dead_code)]
 
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Write;
 use std::mem::ManuallyDrop;
+use std::path::Path;
 use std::ops::{AddAssign, Deref, DerefMut, Index, IndexMut};
 
+use crate::graphics::{Color, ColorCutQuantizer, ColorInt, ColorUtils};
+use crate::object::Rect;
+use zune_jpeg::zune_core::colorspace::ColorSpace;
+use zune_jpeg::zune_core::options::DecoderOptions;
+use zune_jpeg::JpegDecoder;
+
 /// A Pixel in an Image.
 #[repr(transparent)]
 pub struct Pixel {
@@ -25,18 +35,23 @@ impl IndexMut<usize> for Pixel {
 
 impl AddAssign for Pixel {
     fn add_assign(&mut self, pixel: Self) {
-        let alpha = pixel[3] as u64;
-        /* red */
-        self[0] = ((alpha * pixel[0] as u64) + ((255 - alpha) * self[0] as u64)).div_ceil(255) as u8;
-        /* green */
-        self[1] = ((alpha * pixel[1] as u64) + ((255 - alpha) * self[1] as u64)).div_ceil(255) as u8;
-        /* blue */
-        self[2] = ((alpha * pixel[2] as u64) + ((255 - alpha) * self[2] as u64)).div_ceil(255) as u8;
-        /* alpha */
-        self[3] = (alpha * 255 + ((255 - alpha) * self[3] as u64)).div_ceil(255) as u8;
+        blend(&mut self.buf, &pixel.buf);
     }
 }
 
+/// Blends the RGBA pixel `src` over the RGBA pixel `dst`.
+fn blend(dst: &mut [u8], src: &[u8]) {
+    let alpha = src[3] as u64;
+    /* red */
+    dst[0] = ((alpha * src[0] as u64) + ((255 - alpha) * dst[0] as u64)).div_ceil(255) as u8;
+    /* green */
+    dst[1] = ((alpha * src[1] as u64) + ((255 - alpha) * dst[1] as u64)).div_ceil(255) as u8;
+    /* blue */
+    dst[2] = ((alpha * src[2] as u64) + ((255 - alpha) * dst[2] as u64)).div_ceil(255) as u8;
+    /* alpha */
+    dst[3] = (alpha * 255 + ((255 - alpha) * dst[3] as u64)).div_ceil(255) as u8;
+}
+
 pub struct Row {
     pub length: usize,
     pixels: Vec<Pixel>,
@@ -68,6 +83,128 @@ impl Deref for Row {
     }
 }
 
+/// Errors returned by [Image] operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageError {
+    /// The requested size is smaller than the source image.
+    TargetTooSmall { width: usize, height: usize },
+    /// A file could not be read or written.
+    Io { kind: std::io::ErrorKind, message: String },
+    /// The encoder rejected the image, e.g. because it is too large for the format.
+    Encoding(String),
+    /// The input is not a valid image of the expected format.
+    Decoding(String),
+    /// A pixel buffer does not have the length its dimensions require.
+    BufferSize { expected: usize, actual: usize },
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::TargetTooSmall { width, height } => {
+                write!(f, "target size {width}x{height} is smaller than the image")
+            }
+            ImageError::Io { message, .. } => write!(f, "failed to access image file: {message}"),
+            ImageError::Encoding(message) => write!(f, "failed to encode image: {message}"),
+            ImageError::Decoding(message) => write!(f, "failed to decode image: {message}"),
+            ImageError::BufferSize { expected, actual } => {
+                write!(f, "expected a buffer of {expected} bytes, got {actual}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+impl From<std::io::Error> for ImageError {
+    fn from(err: std::io::Error) -> Self {
+        ImageError::Io { kind: err.kind(), message: err.to_string() }
+    }
+}
+
+impl From<png::EncodingError> for ImageError {
+    fn from(err: png::EncodingError) -> Self {
+        match err {
+            png::EncodingError::IoError(err) => err.into(),
+            err => ImageError::Encoding(err.to_string()),
+        }
+    }
+}
+
+impl From<png::DecodingError> for ImageError {
+    fn from(err: png::DecodingError) -> Self {
+        match err {
+            png::DecodingError::IoError(err) => err.into(),
+            err => ImageError::Decoding(err.to_string()),
+        }
+    }
+}
+
+impl From<jpeg_encoder::EncodingError> for ImageError {
+    fn from(err: jpeg_encoder::EncodingError) -> Self {
+        ImageError::Encoding(err.to_string())
+    }
+}
+
+#[cfg(feature = "gif")]
+impl From<gif::DecodingError> for ImageError {
+    fn from(err: gif::DecodingError) -> Self {
+        ImageError::Decoding(err.to_string())
+    }
+}
+
+/// How [Image::resize] samples the source image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResizeFilter {
+    /// Take each pixel from the nearest source pixel. Fast, and keeps hard edges.
+    #[default]
+    Nearest,
+    /// Interpolate linearly between the four nearest source pixels.
+    Bilinear,
+}
+
+/// Where to place an image inside a larger area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    #[default]
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Returns the offset of an item of size `inner` placed inside `outer` along each axis.
+    fn offset(&self, inner: (usize, usize), outer: (usize, usize)) -> (usize, usize) {
+        let (free_x, free_y) = (outer.0 - inner.0, outer.1 - inner.1);
+        let x = match self {
+            Anchor::TopLeft | Anchor::Left | Anchor::BottomLeft => 0,
+            Anchor::Top | Anchor::Center | Anchor::Bottom => free_x / 2,
+            Anchor::TopRight | Anchor::Right | Anchor::BottomRight => free_x,
+        };
+        let y = match self {
+            Anchor::TopLeft | Anchor::Top | Anchor::TopRight => 0,
+            Anchor::Left | Anchor::Center | Anchor::Right => free_y / 2,
+            Anchor::BottomLeft | Anchor::Bottom | Anchor::BottomRight => free_y,
+        };
+        (x, y)
+    }
+}
+
+/// Returns the RGBA bytes of `color`.
+fn color_to_rgba(color: ColorInt) -> [u8; 4] {
+    [color.red(), color.green(), color.blue(), color.alpha()]
+}
+
+/// An RGBA image.
+///
+/// Pixels are stored tightly packed, one byte per channel in `R, G, B, A` order, with rows laid
+/// out top to bottom. Row `y` therefore starts at byte `y * stride()`.
 #[derive(Default)]
 pub struct Image {
     pub width: usize,
@@ -84,6 +221,216 @@ impl Image {
         }
     }
 
+    /// Creates a `width` x `height` image filled with `color`.
+    pub fn solid(width: usize, height: usize, color: ColorInt) -> Image {
+        Image {
+            width, height, format: String::new(), rows: vec![], buf: color_to_rgba(color).repeat(width * height)
+        }
+    }
+
+    /// Creates a `width` x `height` image from one luminance byte per pixel, each expanded to an
+    /// opaque gray pixel. `lum` must hold exactly `width * height` bytes.
+    pub fn from_luminance(width: usize, height: usize, lum: Vec<u8>) -> Result<Image, ImageError> {
+        if lum.len() != width * height {
+            return Err(ImageError::BufferSize { expected: width * height, actual: lum.len() });
+        }
+        let buf = lum.iter().flat_map(|&v| [v, v, v, 255]).collect();
+        Ok(Image {
+            width, height, format: String::new(), rows: vec![], buf
+        })
+    }
+
+    /// Creates a `width` x `height` checkerboard of `size` x `size` cells alternating between
+    /// `a` and `b`, starting with `a` in the top-left corner.
+    pub fn checkerboard(width: usize, height: usize, size: usize, a: ColorInt, b: ColorInt) -> Image {
+        let size = size.max(1);
+        let (a, b) = (color_to_rgba(a), color_to_rgba(b));
+        let mut buf = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            for x in 0..width {
+                let cell = if (x / size + y / size).is_multiple_of(2) { a } else { b };
+                buf.extend_from_slice(&cell);
+            }
+        }
+        Image {
+            width, height, format: String::new(), rows: vec![], buf
+        }
+    }
+
+    /// Returns the number of bytes between the start of two consecutive rows.
+    /// Rows are tightly packed, so this is always `width * 4`.
+    pub fn stride(&self) -> usize {
+        self.width * 4
+    }
+
+    /// Returns the bytes of row `y`, or `None` if the row is out of range.
+    pub fn row_bytes(&self, y: usize) -> Option<&[u8]> {
+        if y >= self.height {
+            return None;
+        }
+        let start = y * self.stride();
+        self.buf.get(start..start + self.stride())
+    }
+
+    /// Decodes a PNG image. Grayscale, palette and 16 bit images are expanded to 8 bit RGBA.
+    pub fn from_png_bytes(bytes: &[u8]) -> Result<Image, ImageError> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info()?;
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data)?;
+        data.truncate(info.buffer_size());
+
+        let buf = match info.color_type {
+            png::ColorType::Rgba => data,
+            png::ColorType::Rgb => {
+                data.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect()
+            }
+            png::ColorType::GrayscaleAlpha => {
+                data.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect()
+            }
+            png::ColorType::Grayscale => data.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+            png::ColorType::Indexed => {
+                return Err(ImageError::Decoding("palette was not expanded".to_string()))
+            }
+        };
+        let (width, height) = (info.width as usize, info.height as usize);
+        Ok(Image {
+            width, height, format: String::from("png"), rows: vec![], buf
+        })
+    }
+
+    /// Reads and decodes the PNG image at `path`, see [from_png_bytes](Image::from_png_bytes).
+    pub fn from_png_file<P: AsRef<Path>>(path: P) -> Result<Image, ImageError> {
+        Self::from_png_bytes(&std::fs::read(path)?)
+    }
+
+    /// Decodes a baseline or progressive JPEG image into RGBA. JPEG has no alpha channel, so
+    /// every pixel is fully opaque.
+    pub fn from_jpeg_bytes(bytes: &[u8]) -> Result<Image, ImageError> {
+        let options = DecoderOptions::default().jpeg_set_out_colorspace(ColorSpace::RGBA);
+        let mut decoder = JpegDecoder::new_with_options(bytes, options);
+        decoder.decode_headers().map_err(|err| ImageError::Decoding(err.to_string()))?;
+        let (width, height) = decoder
+            .dimensions()
+            .ok_or_else(|| ImageError::Decoding("missing image dimensions".to_string()))?;
+        let mut buf = decoder.decode().map_err(|err| ImageError::Decoding(err.to_string()))?;
+        if buf.len() != width * height * 4 {
+            return Err(ImageError::Decoding("unexpected decoded size".to_string()));
+        }
+        for pixel in buf.chunks_exact_mut(4) {
+            pixel[3] = 255;
+        }
+        Ok(Image {
+            width, height, format: String::from("jpeg"), rows: vec![], buf
+        })
+    }
+
+    /// Reads and decodes the JPEG image at `path`, see [from_jpeg_bytes](Image::from_jpeg_bytes).
+    pub fn from_jpeg_file<P: AsRef<Path>>(path: P) -> Result<Image, ImageError> {
+        Self::from_jpeg_bytes(&std::fs::read(path)?)
+    }
+
+    /// Encodes this image as an 8-bit RGBA PNG into `writer`, one row at a time, so large images
+    /// never need a second in-memory copy of the encoded data.
+    pub fn write_png<W: Write>(&self, writer: W) -> Result<(), ImageError> {
+        self.check_buffer_size()?;
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut png = encoder.write_header()?;
+        let mut stream = png.stream_writer()?;
+        for y in 0..self.height {
+            stream.write_all(self.row_bytes(y).unwrap_or_default())?;
+        }
+        stream.finish()?;
+        Ok(())
+    }
+
+    /// Checks that `buf` holds exactly `width * height` RGBA pixels.
+    fn check_buffer_size(&self) -> Result<(), ImageError> {
+        let expected = self.width * self.height * 4;
+        if self.buf.len() != expected {
+            return Err(ImageError::BufferSize { expected, actual: self.buf.len() });
+        }
+        Ok(())
+    }
+
+    /// Encodes this image as an 8-bit RGBA PNG.
+    pub fn to_png_bytes(&self) -> Result<Vec<u8>, ImageError> {
+        let mut bytes = vec![];
+        self.write_png(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Encodes this image as an 8-bit RGBA PNG and writes it to `path`.
+    pub fn to_png_file<P: AsRef<Path>>(&self, path: P) -> Result<(), ImageError> {
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write_png(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Encodes this image as a JPEG with the given `quality` (1 to 100). JPEG has no alpha
+    /// channel, so the alpha of every pixel is dropped.
+    pub fn to_jpeg_bytes(&self, quality: u8) -> Result<Vec<u8>, ImageError> {
+        self.check_buffer_size()?;
+        let (width, height) = match (u16::try_from(self.width), u16::try_from(self.height)) {
+            (Ok(width), Ok(height)) => (width, height),
+            _ => return Err(ImageError::Encoding("image is too large for JPEG".to_string())),
+        };
+        let mut bytes = vec![];
+        let encoder = jpeg_encoder::Encoder::new(&mut bytes, quality.clamp(1, 100));
+        encoder.encode(&self.buf, width, height, jpeg_encoder::ColorType::Rgba)?;
+        Ok(bytes)
+    }
+
+    /// Decodes every frame of a GIF animation. Each frame is composited onto the previous ones
+    /// according to its disposal method, so every returned image is a full-size snapshot of
+    /// the animation at that frame.
+    #[cfg(feature = "gif")]
+    pub fn frames_from_gif<R: std::io::Read>(reader: R) -> Result<Vec<Image>, ImageError> {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(reader)?;
+
+        let (width, height) = (decoder.width() as usize, decoder.height() as usize);
+        let mut screen = Image::new(width, height, String::from("gif"));
+        let mut frames = vec![];
+
+        while let Some(frame) = decoder.read_next_frame()? {
+            let previous = screen.buf.clone();
+            let (left, top) = (frame.left as usize, frame.top as usize);
+            let (frame_width, frame_height) = (frame.width as usize, frame.height as usize);
+
+            for y in 0..frame_height.min(height.saturating_sub(top)) {
+                for x in 0..frame_width.min(width.saturating_sub(left)) {
+                    let src = &frame.buffer[(y * frame_width + x) * 4..][..4];
+                    if src[3] != 0 {
+                        let dst = ((top + y) * width + left + x) * 4;
+                        screen.buf[dst..dst + 4].copy_from_slice(src);
+                    }
+                }
+            }
+            frames.push(screen.clone());
+
+            match frame.dispose {
+                gif::DisposalMethod::Background => {
+                    for y in top..(top + frame_height).min(height) {
+                        let start = (y * width + left.min(width)) * 4;
+                        let end = (y * width + (left + frame_width).min(width)) * 4;
+                        screen.buf[start..end].fill(0);
+                    }
+                }
+                gif::DisposalMethod::Previous => screen.buf = previous,
+                _ => {}
+            }
+        }
+
+        Ok(frames)
+    }
+
     pub fn get_row(&mut self, index: usize) -> Option<&mut Row> {
         let start = index * self.width * 4;
         let end = start + self.width * 4;
@@ -101,8 +448,580 @@ impl Image {
         self.rows.get_mut(length)
     }
 
-    pub fn as_color_ints(&mut self) -> Vec<i32> {
-        todo!()
+    /// Returns the pixels of this image as packed ARGB color ints, row by row.
+    pub fn as_color_ints(&self) -> Vec<i32> {
+        self.buf
+            .chunks_exact(4)
+            .take(self.width * self.height)
+            .map(|p| *Color::argb(p[3], p[0], p[1], p[2]))
+            .collect()
+    }
+
+    /// Returns a `width` x `height` copy of this image placed at `anchor`, with the uncovered area
+    /// filled with `fill`.
+    ///
+    /// Returns [ImageError::TargetTooSmall] if either dimension is smaller than the image.
+    pub fn pad(&self, width: usize, height: usize, anchor: Anchor, fill: ColorInt) -> Result<Image, ImageError> {
+        if width < self.width || height < self.height {
+            return Err(ImageError::TargetTooSmall { width, height });
+        }
+
+        let mut buf = color_to_rgba(fill).repeat(width * height);
+        if self.width == 0 || self.height == 0 {
+            // Nothing to copy, e.g. the result of cropping to an empty region
+            return Ok(Image {
+                width, height, format: self.format.clone(), rows: vec![], buf
+            });
+        }
+
+        let (x, y) = anchor.offset((self.width, self.height), (width, height));
+        let row_length = self.width * 4;
+        for (i, row) in self.buf.chunks_exact(row_length).take(self.height).enumerate() {
+            let start = ((y + i) * width + x) * 4;
+            buf[start..start + row_length].copy_from_slice(row);
+        }
+
+        Ok(Image {
+            width, height, format: self.format.clone(), rows: vec![], buf
+        })
+    }
+
+    /// Returns an image holding the per-channel absolute difference between the RGB components
+    /// of this image and `other`, with alpha set to 255.
+    ///
+    /// # Panics
+    /// Panics if the images do not have the same dimensions.
+    pub fn difference(&self, other: &Image) -> Image {
+        self.assert_same_size(other);
+        let mut buf = Vec::with_capacity(self.buf.len());
+        for (a, b) in self.buf.chunks_exact(4).zip(other.buf.chunks_exact(4)) {
+            buf.extend_from_slice(&[a[0].abs_diff(b[0]), a[1].abs_diff(b[1]), a[2].abs_diff(b[2]), 255]);
+        }
+        Image {
+            width: self.width, height: self.height, format: self.format.clone(), rows: vec![], buf
+        }
+    }
+
+    /// Returns the peak signal-to-noise ratio in decibels between this image and `other`,
+    /// computed over all four channels. Identical images return [f64::INFINITY].
+    ///
+    /// # Panics
+    /// Panics if the images do not have the same dimensions.
+    pub fn psnr(&self, other: &Image) -> f64 {
+        self.assert_same_size(other);
+        if self.buf.is_empty() {
+            return f64::INFINITY;
+        }
+        let sum: f64 = self.buf.iter().zip(other.buf.iter())
+            .map(|(&a, &b)| (a as f64 - b as f64).powi(2))
+            .sum();
+        let mse = sum / self.buf.len() as f64;
+        if mse == 0.0 {
+            return f64::INFINITY;
+        }
+        10.0 * f64::log10(255.0 * 255.0 / mse)
+    }
+
+    fn assert_same_size(&self, other: &Image) {
+        assert!(self.width == other.width && self.height == other.height,
+            "image sizes differ: {}x{} and {}x{}", self.width, self.height, other.width, other.height);
+    }
+
+    /// Alpha-blends the `src_rect` region of `src` onto this image with its top-left corner at
+    /// `dst`. Parts of the region outside of either image are clipped.
+    pub fn blend_region(&mut self, src: &Image, src_rect: &Rect, dst: (i32, i32)) {
+        // Clip the region to the source image
+        let mut left = src_rect.left.max(0);
+        let mut top = src_rect.top.max(0);
+        let right = src_rect.right.min(src.width as i32);
+        let bottom = src_rect.bottom.min(src.height as i32);
+
+        // Move the destination by however much was clipped, then clip to this image
+        let mut x = dst.0 + (left - src_rect.left);
+        let mut y = dst.1 + (top - src_rect.top);
+        if x < 0 {
+            left -= x;
+            x = 0;
+        }
+        if y < 0 {
+            top -= y;
+            y = 0;
+        }
+        let width = (right - left).min(self.width as i32 - x);
+        let height = (bottom - top).min(self.height as i32 - y);
+        if width <= 0 || height <= 0 {
+            return;
+        }
+
+        let dst_stride = self.stride();
+        let (left, length) = (left as usize * 4, width as usize * 4);
+        for row in 0..height as usize {
+            // Rows missing from a truncated source buffer are left untouched
+            let Some(src_row) = src.row_bytes(top as usize + row) else {
+                break;
+            };
+            let src_row = &src_row[left..left + length];
+            let dst_start = (y as usize + row) * dst_stride + x as usize * 4;
+            let dst_row = &mut self.buf[dst_start..dst_start + length];
+            for (d, s) in dst_row.chunks_exact_mut(4).zip(src_row.chunks_exact(4)) {
+                blend(d, s);
+            }
+        }
+    }
+
+    /// Alpha-blends `color` over every pixel inside `rect`. Parts of `rect` outside of this image
+    /// are clipped.
+    pub fn fill_rect(&mut self, rect: &Rect, color: ColorInt) {
+        let left = rect.left.clamp(0, self.width as i32) as usize;
+        let right = rect.right.clamp(0, self.width as i32) as usize;
+        let top = rect.top.clamp(0, self.height as i32) as usize;
+        let bottom = rect.bottom.clamp(0, self.height as i32) as usize;
+        if left >= right {
+            return;
+        }
+
+        let src = color_to_rgba(color);
+        let stride = self.stride();
+        for y in top..bottom {
+            for pixel in self.buf[y * stride + left * 4..y * stride + right * 4].chunks_exact_mut(4) {
+                blend(pixel, &src);
+            }
+        }
+    }
+
+    /// Recolors every pixel with the RGB components of `color`, keeping the shape of the image.
+    /// The alpha of each pixel is scaled by the alpha of `color`.
+    pub fn tint(&mut self, color: ColorInt) {
+        let alpha = color.alpha() as u32;
+        for pixel in self.buf.chunks_exact_mut(4) {
+            pixel[0] = color.red();
+            pixel[1] = color.green();
+            pixel[2] = color.blue();
+            pixel[3] = (pixel[3] as u32 * alpha).div_ceil(255) as u8;
+        }
+    }
+
+    /// Returns an opaque copy of this image composited over a solid `background`, e.g. before
+    /// encoding to a format without alpha. The alpha of `background` is ignored.
+    pub fn flatten_onto(&self, background: ColorInt) -> Image {
+        let background = Color::rgb(background.red(), background.green(), background.blue());
+        let mut out = self.clone();
+        for pixel in out.buf.chunks_exact_mut(4) {
+            let color = Color::argb(pixel[3], pixel[0], pixel[1], pixel[2]);
+            let flat: ColorInt = ColorUtils::composite_colors(color, background).into();
+            pixel.copy_from_slice(&color_to_rgba(flat));
+        }
+        out
+    }
+
+    /// Returns a copy of this image scaled down, preserving aspect-ratio, so that it is at most
+    /// `max_width` wide and `max_height` tall. Images which already fit are returned as is,
+    /// never upscaled.
+    pub fn scale_to_fit(&self, max_width: usize, max_height: usize) -> Image {
+        if self.width <= max_width && self.height <= max_height {
+            return self.clone();
+        }
+
+        let scale = f64::min(max_width as f64 / self.width as f64, max_height as f64 / self.height as f64);
+        let width = ((self.width as f64 * scale).round() as usize).clamp(1, max_width.max(1));
+        let height = ((self.height as f64 * scale).round() as usize).clamp(1, max_height.max(1));
+        self.resample_nearest(width, height)
+    }
+
+    /// Returns a copy of this image scaled down, preserving aspect-ratio, so that it holds at
+    /// most `area` pixels. Images which already fit, or an `area` of 0 or less, return a copy
+    /// of this image.
+    pub fn scale_to_fit_area(&self, area: i32) -> Image {
+        let pixels = self.width * self.height;
+        if area <= 0 || pixels <= area as usize {
+            return self.clone();
+        }
+        self.scale_by((area as f64 / pixels as f64).sqrt())
+    }
+
+    /// Returns a copy of this image scaled down, preserving aspect-ratio, so that neither side
+    /// is longer than `max`. Images which already fit, or a `max` of 0 or less, return a copy
+    /// of this image.
+    pub fn scale_to_max_dimension(&self, max: i32) -> Image {
+        let longest = self.width.max(self.height);
+        if max <= 0 || longest <= max as usize {
+            return self.clone();
+        }
+        self.scale_by(max as f64 / longest as f64)
+    }
+
+    /// Returns a copy of this image scaled down by `ratio`, rounding sizes down so the result
+    /// never exceeds the requested bound, but keeping at least one pixel per side.
+    fn scale_by(&self, ratio: f64) -> Image {
+        let width = ((self.width as f64 * ratio) as usize).max(1);
+        let height = ((self.height as f64 * ratio) as usize).max(1);
+        self.resample_nearest(width, height)
+    }
+
+    /// Returns a copy of this image resized to `width` x `height` using `filter`. All four
+    /// channels, alpha included, are resampled. An empty image resizes to a transparent one.
+    pub fn resize(&self, width: usize, height: usize, filter: ResizeFilter) -> Image {
+        if self.width == 0 || self.height == 0 {
+            return Image::new(width, height, self.format.clone());
+        }
+        match filter {
+            ResizeFilter::Nearest => self.resample_nearest(width, height),
+            ResizeFilter::Bilinear => self.resample_bilinear(width, height),
+        }
+    }
+
+    /// Returns a `width` x `height` copy of this image, interpolating each pixel from the four
+    /// source pixels around its center.
+    fn resample_bilinear(&self, width: usize, height: usize) -> Image {
+        // Maps the center of destination pixel `i` back into source space, returning the two
+        // neighbouring source indices and the weight of the second one.
+        let sample = |i: usize, dst: usize, src: usize| {
+            let pos = ((i as f64 + 0.5) * src as f64 / dst as f64 - 0.5).max(0.0);
+            let low = (pos as usize).min(src - 1);
+            (low, (low + 1).min(src - 1), pos - low as f64)
+        };
+
+        let mut buf = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            let (y0, y1, fy) = sample(y, height, self.height);
+            for x in 0..width {
+                let (x0, x1, fx) = sample(x, width, self.width);
+                let pixel = |sx: usize, sy: usize| &self.buf[(sy * self.width + sx) * 4..][..4];
+                let (p00, p10, p01, p11) = (pixel(x0, y0), pixel(x1, y0), pixel(x0, y1), pixel(x1, y1));
+                for c in 0..4 {
+                    let top = p00[c] as f64 * (1.0 - fx) + p10[c] as f64 * fx;
+                    let bottom = p01[c] as f64 * (1.0 - fx) + p11[c] as f64 * fx;
+                    buf.push((top * (1.0 - fy) + bottom * fy).round() as u8);
+                }
+            }
+        }
+        Image {
+            width, height, format: self.format.clone(), rows: vec![], buf
+        }
+    }
+
+    /// Returns a `width` x `height` copy of this image, taking each pixel from the nearest
+    /// source pixel.
+    fn resample_nearest(&self, width: usize, height: usize) -> Image {
+        let mut buf = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            let sy = (2 * y + 1) * self.height / (2 * height);
+            for x in 0..width {
+                let sx = (2 * x + 1) * self.width / (2 * width);
+                let start = (sy * self.width + sx) * 4;
+                buf.extend_from_slice(&self.buf[start..start + 4]);
+            }
+        }
+        Image {
+            width, height, format: self.format.clone(), rows: vec![], buf
+        }
+    }
+
+    /// Returns a copy of this image scaled up by `factor`, turning every pixel into an exact
+    /// `factor` x `factor` block. Suited for pixel art. A `factor` of 0 is treated as 1.
+    pub fn scale_integer(&self, factor: u32) -> Image {
+        let factor = factor.max(1) as usize;
+        let (width, height) = (self.width * factor, self.height * factor);
+        let mut buf = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            let row = &self.buf[(y / factor) * self.width * 4..][..self.width * 4];
+            for pixel in row.chunks_exact(4) {
+                for _ in 0..factor {
+                    buf.extend_from_slice(pixel);
+                }
+            }
+        }
+        Image {
+            width, height, format: self.format.clone(), rows: vec![], buf
+        }
+    }
+
+    /// Returns a copy of this image scaled down by `factor`, averaging every `factor` x `factor`
+    /// block into one pixel. Pixels past the last whole block are dropped. A `factor` of 0 is
+    /// treated as 1.
+    pub fn downscale_integer(&self, factor: u32) -> Image {
+        let factor = factor.max(1) as usize;
+        let (width, height) = (self.width / factor, self.height / factor);
+        let count = (factor * factor) as u32;
+        let mut buf = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0u32; 4];
+                for sy in y * factor..(y + 1) * factor {
+                    let start = (sy * self.width + x * factor) * 4;
+                    for pixel in self.buf[start..start + factor * 4].chunks_exact(4) {
+                        for c in 0..4 {
+                            sum[c] += pixel[c] as u32;
+                        }
+                    }
+                }
+                buf.extend(sum.map(|channel| ((channel + count / 2) / count) as u8));
+            }
+        }
+        Image {
+            width, height, format: self.format.clone(), rows: vec![], buf
+        }
+    }
+
+    /// Returns a copy of the pixels inside `region`, clamped to the bounds of this image. An
+    /// empty region, or one entirely outside of this image, yields an empty image.
+    pub fn crop(&self, region: &Rect) -> Image {
+        let left = region.left.clamp(0, self.width as i32) as usize;
+        let right = region.right.clamp(0, self.width as i32) as usize;
+        let top = region.top.clamp(0, self.height as i32) as usize;
+        let bottom = region.bottom.clamp(0, self.height as i32) as usize;
+        if region.is_empty() || left >= right || top >= bottom {
+            return Image::new(0, 0, self.format.clone());
+        }
+
+        let (width, height) = (right - left, bottom - top);
+        let mut buf = Vec::with_capacity(width * height * 4);
+        for y in top..bottom {
+            buf.extend_from_slice(&self.buf[(y * self.width + left) * 4..(y * self.width + right) * 4]);
+        }
+        Image {
+            width, height, format: self.format.clone(), rows: vec![], buf
+        }
+    }
+
+    /// Returns the number of distinct RGBA colors among the pixels inside `rect`. The parts of
+    /// `rect` outside of this image are ignored.
+    pub fn unique_colors_in_rect(&self, rect: &Rect) -> usize {
+        let left = rect.left.clamp(0, self.width as i32) as usize;
+        let right = rect.right.clamp(0, self.width as i32) as usize;
+        let top = rect.top.clamp(0, self.height as i32) as usize;
+        let bottom = rect.bottom.clamp(0, self.height as i32) as usize;
+        if left >= right {
+            return 0;
+        }
+
+        let mut colors = HashSet::new();
+        for y in top..bottom {
+            let row = &self.buf[y * self.stride()..(y + 1) * self.stride()];
+            colors.extend(row[left * 4..right * 4].chunks_exact(4));
+        }
+        colors.len()
+    }
+
+    /// Replaces the contiguous region of pixels around `(x, y)` whose color is within CIEDE2000
+    /// distance `tolerance` of the seed pixel with `fill`. Pixels are connected through their
+    /// four direct neighbours. Nothing happens if the seed point is outside the image.
+    pub fn flood_fill(&mut self, x: usize, y: usize, fill: ColorInt, tolerance: f64) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let lab_at = |buf: &[u8], index: usize| {
+            let mut lab = [0f64;3];
+            ColorUtils::rgb_to_lab(buf[index * 4], buf[index * 4 + 1], buf[index * 4 + 2], &mut lab);
+            lab
+        };
+        let seed = lab_at(&self.buf, y * self.width + x);
+        let fill = color_to_rgba(fill);
+
+        let mut visited = vec![false; self.width * self.height];
+        let mut stack = vec![(x, y)];
+        visited[y * self.width + x] = true;
+        while let Some((x, y)) = stack.pop() {
+            let index = y * self.width + x;
+            if ColorUtils::lab_distance_ciede2000(&seed, &lab_at(&self.buf, index)) > tolerance {
+                continue;
+            }
+            self.buf[index * 4..index * 4 + 4].copy_from_slice(&fill);
+
+            let neighbours = [
+                (x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)
+            ];
+            for (nx, ny) in neighbours {
+                if nx < self.width && ny < self.height && !visited[ny * self.width + nx] {
+                    visited[ny * self.width + nx] = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+    }
+
+    /// Replaces every pixel whose color is within CIEDE2000 distance `tolerance` of `from` with
+    /// `to`. Alpha is ignored when matching. The pixel keeps its alpha when `to` is opaque,
+    /// otherwise it takes the alpha of `to`.
+    pub fn replace_color(&mut self, from: ColorInt, to: ColorInt, tolerance: f64) {
+        let mut from_lab = [0f64;3];
+        ColorUtils::color_to_lab(from, &mut from_lab);
+        let to = color_to_rgba(to);
+
+        let mut lab = [0f64;3];
+        for pixel in self.buf.chunks_exact_mut(4) {
+            ColorUtils::rgb_to_lab(pixel[0], pixel[1], pixel[2], &mut lab);
+            if ColorUtils::lab_distance_ciede2000(&from_lab, &lab) <= tolerance {
+                pixel[..3].copy_from_slice(&to[..3]);
+                if to[3] != 255 {
+                    pixel[3] = to[3];
+                }
+            }
+        }
+    }
+
+    /// Returns a copy of this image blurred with a `(2 * radius + 1)` wide box filter, applied
+    /// horizontally then vertically. Edge pixels are repeated past the border.
+    pub fn box_blur(&self, radius: usize) -> Image {
+        let mut out = self.clone();
+        if radius == 0 || self.width == 0 || self.height == 0 {
+            return out;
+        }
+
+        let stride = self.width * 4;
+        let mut horizontal = vec![0f32; self.buf.len()];
+        for (y, row) in horizontal.chunks_exact_mut(stride).enumerate() {
+            self.box_blur_row(y, radius, row);
+        }
+        for (y, row) in out.buf.chunks_exact_mut(stride).enumerate() {
+            self.box_blur_column(&horizontal, y, radius, row);
+        }
+        out
+    }
+
+    /// Same as [box_blur](Image::box_blur), but blurs the rows, then the columns, across
+    /// threads. The result is identical.
+    #[cfg(feature = "parallel")]
+    pub fn par_box_blur(&self, radius: usize) -> Image {
+        use rayon::prelude::*;
+
+        let mut out = self.clone();
+        if radius == 0 || self.width == 0 || self.height == 0 {
+            return out;
+        }
+
+        let stride = self.width * 4;
+        let mut horizontal = vec![0f32; self.buf.len()];
+        horizontal.par_chunks_exact_mut(stride).enumerate().for_each(|(y, row)| {
+            self.box_blur_row(y, radius, row);
+        });
+        out.buf.par_chunks_exact_mut(stride).enumerate().for_each(|(y, row)| {
+            self.box_blur_column(&horizontal, y, radius, row);
+        });
+        out
+    }
+
+    /// Horizontal pass of [box_blur](Image::box_blur): writes the blurred channels of row `y`
+    /// into `out`.
+    fn box_blur_row(&self, y: usize, radius: usize, out: &mut [f32]) {
+        let width = self.width;
+        let window = (2 * radius + 1) as f32;
+        for x in 0..width {
+            for c in 0..4 {
+                let sum: f32 = (0..=2 * radius)
+                    .map(|k| (x + k).saturating_sub(radius).min(width - 1))
+                    .map(|sx| self.buf[(y * width + sx) * 4 + c] as f32)
+                    .sum();
+                out[x * 4 + c] = sum / window;
+            }
+        }
+    }
+
+    /// Vertical pass of [box_blur](Image::box_blur): writes row `y` of the columns of the
+    /// horizontally blurred `horizontal` into `out`.
+    fn box_blur_column(&self, horizontal: &[f32], y: usize, radius: usize, out: &mut [u8]) {
+        let (width, height) = (self.width, self.height);
+        let window = (2 * radius + 1) as f32;
+        for x in 0..width {
+            for c in 0..4 {
+                let sum: f32 = (0..=2 * radius)
+                    .map(|k| (y + k).saturating_sub(radius).min(height - 1))
+                    .map(|sy| horizontal[(sy * width + x) * 4 + c])
+                    .sum();
+                out[x * 4 + c] = (sum / window).round() as u8;
+            }
+        }
+    }
+
+    /// Returns a sharpened copy of this image using an unsharp mask: each color channel is pushed
+    /// away from a 3x3 box blurred copy by `amount` times the difference. Alpha is left as is.
+    pub fn sharpen(&self, amount: f32) -> Image {
+        let blurred = self.box_blur(1);
+        let mut out = self.clone();
+        for (pixel, soft) in out.buf.chunks_exact_mut(4).zip(blurred.buf.chunks_exact(4)) {
+            for c in 0..3 {
+                let value = pixel[c] as f32;
+                pixel[c] = (value + amount * (value - soft[c] as f32)).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+        out
+    }
+
+    /// Converts this image to indexed form, returning one palette index per pixel (row by row)
+    /// and a palette of at most `max_colors` opaque colors, capped at 256.
+    ///
+    /// The palette is built with [ColorCutQuantizer] and every pixel is mapped to its nearest
+    /// palette entry in RGB space. Alpha is ignored.
+    pub fn to_indexed(&self, max_colors: usize) -> (Vec<u8>, Vec<ColorInt>) {
+        let pixels = self.as_color_ints();
+        let max_colors = max_colors.clamp(1, 256) as i32;
+        let quantizer = ColorCutQuantizer::new(pixels.clone(), max_colors, vec![]);
+        let palette: Vec<ColorInt> = quantizer
+            .get_quantized_colors()
+            .iter()
+            .map(|swatch| swatch.get_rgb())
+            .collect();
+        let indices = pixels
+            .into_iter()
+            .map(|color| nearest_color_index(&palette, color.into()) as u8)
+            .collect();
+        (indices, palette)
+    }
+
+    /// Returns a copy of this image with every pixel replaced by its nearest color in `palette`
+    /// in RGB space. Alpha is kept as is. Returns a plain copy if `palette` is empty.
+    pub fn remap_to_palette(&self, palette: &[ColorInt]) -> Image {
+        let mut out = self.clone();
+        if !palette.is_empty() {
+            out.buf.chunks_exact_mut(4).for_each(|pixel| remap_pixel(pixel, palette));
+        }
+        out
+    }
+
+    /// Same as [remap_to_palette](Image::remap_to_palette), but splits the pixels across threads.
+    #[cfg(feature = "parallel")]
+    pub fn par_remap_to_palette(&self, palette: &[ColorInt]) -> Image {
+        use rayon::prelude::*;
+
+        let mut out = self.clone();
+        if !palette.is_empty() {
+            out.buf.par_chunks_exact_mut(4).for_each(|pixel| remap_pixel(pixel, palette));
+        }
+        out
+    }
+}
+
+/// Replaces the RGB channels of `pixel` with its nearest color in `palette`.
+fn remap_pixel(pixel: &mut [u8], palette: &[ColorInt]) {
+    let color = Color::rgb(pixel[0], pixel[1], pixel[2]);
+    let nearest = palette[nearest_color_index(palette, color)];
+    pixel[0] = Color::red(nearest);
+    pixel[1] = Color::green(nearest);
+    pixel[2] = Color::blue(nearest);
+}
+
+/// Returns the index of the entry in `palette` closest to `color` by squared RGB distance.
+fn nearest_color_index(palette: &[ColorInt], color: ColorInt) -> usize {
+    let distance = |other: &ColorInt| {
+        let dr = Color::red(color) as i32 - Color::red(*other) as i32;
+        let dg = Color::green(color) as i32 - Color::green(*other) as i32;
+        let db = Color::blue(color) as i32 - Color::blue(*other) as i32;
+        dr * dr + dg * dg + db * db
+    };
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, other)| distance(other))
+        .map_or(0, |(index, _)| index)
+}
+
+impl Clone for Image {
+    /// Deep-copies the pixel buffer. Rows handed out by [Image::get_row] are not carried over.
+    fn clone(&self) -> Self {
+        Image {
+            width: self.width, height: self.height, format: self.format.clone(), rows: vec![], buf: self.buf.clone()
+        }
     }
 }
 
@@ -211,4 +1130,513 @@ fn test_pixels_in_row() {
 
     let invalid_pixel = row.get_pixel(5);
     assert!(invalid_pixel.is_none());
-}
\ No newline at end of file
+}
+#[test]
+fn test_tint_keeps_alpha() {
+    use crate::graphics::{Color, Swatch};
+
+    let mut img = Image::from((2, 1, vec![
+        10, 20, 30, 255,   40, 50, 60, 0
+    ]));
+    let swatch = Swatch::new(Color::rgb(200, 100, 50), 1);
+    img.tint(swatch.get_rgb());
+    assert_eq!(*img, vec![200, 100, 50, 255,   200, 100, 50, 0]);
+}
+
+#[test]
+fn test_pad_centered() {
+    use crate::graphics::Color;
+
+    let img = Image::from((2, 2, vec![
+        1, 1, 1, 255,   2, 2, 2, 255,
+        3, 3, 3, 255,   4, 4, 4, 255
+    ]));
+    let padded = img.pad(4, 4, Anchor::Center, Color::TRANSPARENT).unwrap();
+    assert_eq!((padded.width, padded.height), (4, 4));
+
+    let t = [0, 0, 0, 0];
+    let expected: Vec<u8> = [
+        t, t, t, t,
+        t, [1, 1, 1, 255], [2, 2, 2, 255], t,
+        t, [3, 3, 3, 255], [4, 4, 4, 255], t,
+        t, t, t, t,
+    ].concat();
+    assert_eq!(*padded, expected);
+
+    assert_eq!(img.pad(1, 4, Anchor::Center, Color::TRANSPARENT).err(),
+        Some(ImageError::TargetTooSmall { width: 1, height: 4 }));
+
+    // An empty image pads to a solid fill
+    let padded = Image::new(0, 0, String::new()).pad(4, 4, Anchor::Center, Color::BLACK).unwrap();
+    assert_eq!((padded.width, padded.height), (4, 4));
+    assert_eq!(*padded, [0, 0, 0, 255].repeat(16));
+}
+
+#[test]
+fn test_row_bytes() {
+    let img = Image::from((2, 3, vec![
+         1,  2,  3,  4,    5,  6,  7,  8,
+
+         9, 10, 11, 12,   13, 14, 15, 16,
+
+        17, 18, 19, 20,   21, 22, 23, 24
+    ]));
+
+    assert_eq!(img.stride(), 8);
+    assert_eq!(img.row_bytes(0), Some(&[1, 2, 3, 4, 5, 6, 7, 8][..]));
+    assert_eq!(img.row_bytes(1), Some(&[9, 10, 11, 12, 13, 14, 15, 16][..]));
+    assert_eq!(img.row_bytes(2), Some(&[17, 18, 19, 20, 21, 22, 23, 24][..]));
+    assert_eq!(img.row_bytes(3), None);
+}
+
+#[test]
+fn test_checkerboard_cells() {
+    use crate::graphics::Color;
+
+    let img = Image::checkerboard(4, 4, 2, Color::RED, Color::WHITE);
+    let r = [255, 0, 0, 255];
+    let w = [255, 255, 255, 255];
+    let expected: Vec<u8> = [
+        r, r, w, w,
+        r, r, w, w,
+        w, w, r, r,
+        w, w, r, r,
+    ].concat();
+    assert_eq!(*img, expected);
+
+    let solid = Image::solid(3, 2, Color::RED);
+    assert_eq!(*solid, r.repeat(6));
+}
+
+#[test]
+fn test_difference_and_psnr() {
+    use crate::graphics::Color;
+
+    let a = Image::checkerboard(4, 4, 1, Color::RED, Color::WHITE);
+    let b = Image::checkerboard(4, 4, 1, Color::RED, Color::WHITE);
+    assert_eq!(a.psnr(&b), f64::INFINITY);
+    assert!(a.difference(&b).chunks_exact(4).all(|pixel| pixel == [0, 0, 0, 255]));
+
+    let mut c = Image::checkerboard(4, 4, 1, Color::RED, Color::WHITE);
+    c[0] = 250;
+    let psnr = a.psnr(&c);
+    assert!(psnr.is_finite() && psnr > 40.0);
+    assert_eq!(a.difference(&c)[0..4], [5, 0, 0, 255]);
+}
+
+#[test]
+fn test_clone_is_deep() {
+    use crate::graphics::Color;
+
+    let original = Image::solid(2, 2, Color::RED);
+    let mut copy = original.clone();
+    copy.tint(Color::GREEN);
+
+    assert_eq!(*original, [255, 0, 0, 255].repeat(4));
+    assert_eq!(*copy, [0, 255, 0, 255].repeat(4));
+}
+
+#[test]
+fn test_blend_region_from_sprite_sheet() {
+    use crate::graphics::Color;
+
+    let blue = Color::rgb(0, 0, 255);
+    // Two 2x2 sprites side by side: red, then blue
+    let sheet = Image::checkerboard(4, 2, 2, Color::RED, blue);
+    let cell = Rect::new(2, 0, 4, 2);
+
+    let mut background = Image::solid(3, 3, Color::WHITE);
+    background.blend_region(&sheet, &cell, (1, 1));
+    let w = [255, 255, 255, 255];
+    let b = [0, 0, 255, 255];
+    let expected: Vec<u8> = [
+        w, w, w,
+        w, b, b,
+        w, b, b,
+    ].concat();
+    assert_eq!(*background, expected);
+
+    // Partly off the top-left corner, only one pixel lands
+    let mut background = Image::solid(3, 3, Color::WHITE);
+    background.blend_region(&sheet, &cell, (-1, -1));
+    let expected: Vec<u8> = [
+        b, w, w,
+        w, w, w,
+        w, w, w,
+    ].concat();
+    assert_eq!(*background, expected);
+}
+
+#[test]
+fn test_write_png_round_trip() {
+    use crate::graphics::Color;
+
+    let img = Image::checkerboard(5, 3, 1, Color::RED, Color::argb(128, 0, 255, 0));
+    let mut out = std::io::Cursor::new(Vec::new());
+    img.write_png(&mut out).unwrap();
+
+    let decoder = png::Decoder::new(out.get_ref().as_slice());
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    assert_eq!((info.width, info.height), (5, 3));
+    assert_eq!(info.color_type, png::ColorType::Rgba);
+    assert_eq!(&buf[..info.buffer_size()], &img.buf[..]);
+}
+
+#[test]
+fn test_sharpen_increases_edge_contrast() {
+    let ramp = [50u8, 50, 50, 100, 150, 200, 200, 200];
+    let buf = ramp.iter().flat_map(|&v| [v, v, v, 255]).collect::<Vec<u8>>();
+    let img = Image::from((8, 1, buf));
+
+    let sharp = img.sharpen(1.0);
+    let red = |x: usize| sharp.buf[x * 4];
+    assert!(red(2) < 50);
+    assert!(red(5) > 200);
+    assert!(red(5) - red(2) > 150);
+    assert!(sharp.buf.chunks_exact(4).all(|p| p[3] == 255));
+}
+
+#[test]
+fn test_scale_to_fit_width_bound() {
+    use crate::graphics::Color;
+
+    let wide = Image::checkerboard(400, 100, 100, Color::RED, Color::WHITE);
+    let thumb = wide.scale_to_fit(100, 100);
+    assert_eq!((thumb.width, thumb.height), (100, 25));
+    assert_eq!(thumb.row_bytes(0).unwrap()[0..4], [255, 0, 0, 255]);
+    assert_eq!(thumb.row_bytes(0).unwrap()[100..104], [255, 255, 255, 255]);
+}
+
+#[test]
+fn test_scale_to_fit_height_bound() {
+    use crate::graphics::Color;
+
+    let tall = Image::solid(100, 400, Color::RED);
+    let thumb = tall.scale_to_fit(100, 50);
+    assert_eq!((thumb.width, thumb.height), (13, 50));
+
+    let small = Image::solid(10, 20, Color::RED);
+    let same = small.scale_to_fit(100, 50);
+    assert_eq!((same.width, same.height), (10, 20));
+}
+
+#[test]
+fn test_flood_fill_quadrant() {
+    let quadrants = [Color::rgb(200, 30, 30), Color::rgb(30, 200, 30), Color::rgb(30, 30, 200), Color::rgb(220, 220, 40)];
+    let mut buf = vec![];
+    for y in 0..4 {
+        for x in 0..4 {
+            buf.extend_from_slice(&color_to_rgba(quadrants[(y / 2) * 2 + x / 2]));
+        }
+    }
+    let original = Image::from((4, 4, buf));
+
+    let mut filled = original.clone();
+    filled.flood_fill(1, 1, Color::WHITE, 1.0);
+    for y in 0..4 {
+        for x in 0..4 {
+            let pixel = &filled.row_bytes(y).unwrap()[x * 4..x * 4 + 4];
+            if x < 2 && y < 2 {
+                assert_eq!(pixel, [255, 255, 255, 255]);
+            } else {
+                assert_eq!(pixel, &original.row_bytes(y).unwrap()[x * 4..x * 4 + 4]);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_from_luminance() {
+    let img = Image::from_luminance(2, 2, vec![0, 64, 128, 255]).unwrap();
+    assert_eq!((img.width, img.height), (2, 2));
+    for (pixel, value) in img.buf.chunks_exact(4).zip([0, 64, 128, 255]) {
+        assert_eq!(pixel, [value, value, value, 255]);
+    }
+
+    assert_eq!(Image::from_luminance(2, 2, vec![0; 3]).err(),
+        Some(ImageError::BufferSize { expected: 4, actual: 3 }));
+}
+
+#[test]
+fn test_unique_colors_in_rect() {
+    let mut img = Image::checkerboard(6, 6, 1, Color::RED, Color::WHITE);
+    img.blend_region(&Image::solid(2, 2, Color::rgb(0, 0, 255)), &Rect::new(0, 0, 2, 2), (4, 4));
+
+    assert_eq!(img.unique_colors_in_rect(&Rect::new(0, 0, 2, 2)), 2);
+    assert_eq!(img.unique_colors_in_rect(&Rect::new(3, 3, 6, 6)), 3);
+    assert_eq!(img.unique_colors_in_rect(&Rect::new(3, 3, 100, 100)), 3);
+    assert_eq!(img.unique_colors_in_rect(&Rect::new(-5, -5, 1, 1)), 1);
+    assert_eq!(img.unique_colors_in_rect(&Rect::new(4, 4, 4, 6)), 0);
+}
+
+#[test]
+fn test_to_indexed_round_trip() {
+    let colors = [
+        Color::rgb(200, 0, 0),
+        Color::rgb(0, 128, 0),
+        Color::rgb(0, 0, 64),
+        Color::rgb(96, 96, 96),
+    ];
+    let mut image = Image::solid(4, 4, colors[0]);
+    for (i, pixel) in image.buf.chunks_exact_mut(4).enumerate() {
+        let color = colors[(i / 2) % 4];
+        pixel.copy_from_slice(&[Color::red(color), Color::green(color), Color::blue(color), 255]);
+    }
+
+    let (indices, palette) = image.to_indexed(4);
+    assert_eq!(palette.len(), 4);
+    assert_eq!(indices.len(), 16);
+    let rebuilt: Vec<i32> = indices.iter().map(|&i| *palette[i as usize]).collect();
+    assert_eq!(rebuilt, image.as_color_ints());
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_par_remap_matches_serial() {
+    let mut image = Image::solid(64, 16, Color::rgb(0, 0, 0));
+    for (i, pixel) in image.buf.chunks_exact_mut(4).enumerate() {
+        let (x, y) = (i % 64, i / 64);
+        pixel[0] = (x * 4) as u8;
+        pixel[1] = (y * 16) as u8;
+        pixel[2] = 255 - (x * 4) as u8;
+    }
+    let palette = [
+        Color::rgb(0, 0, 0),
+        Color::rgb(255, 0, 0),
+        Color::rgb(0, 255, 0),
+        Color::rgb(0, 0, 255),
+    ];
+
+    let serial = image.remap_to_palette(&palette);
+    let parallel = image.par_remap_to_palette(&palette);
+    assert_eq!(serial.buf, parallel.buf);
+    assert!(serial.as_color_ints().iter().all(|c| palette.contains(&ColorInt::from(*c))));
+}
+
+#[test]
+fn test_replace_color() {
+    let background = Color::rgb(240, 240, 240);
+    let ink = Color::rgb(20, 20, 120);
+    let mut image = Image::solid(6, 6, background);
+    for pixel in image.buf.chunks_exact_mut(4).skip(14).take(3) {
+        pixel.copy_from_slice(&[20, 20, 120, 255]);
+    }
+    // Slightly off background with partial alpha.
+    image.buf[0..4].copy_from_slice(&[238, 241, 240, 128]);
+
+    let replacement = Color::rgb(255, 200, 0);
+    image.replace_color(background, replacement, 2.0);
+
+    let colors = image.as_color_ints();
+    assert_eq!(colors[0], *Color::argb(128, 255, 200, 0));
+    for (i, color) in colors.iter().enumerate().skip(1) {
+        let expected = if (14..17).contains(&i) { ink } else { replacement };
+        assert_eq!(*color, *expected);
+    }
+}
+
+#[test]
+fn test_flatten_onto() {
+    let mut image = Image::solid(3, 2, Color::rgb(255, 0, 0));
+    image.buf.chunks_exact_mut(4).for_each(|pixel| pixel[3] = 128);
+
+    let flat = image.flatten_onto(Color::rgb(255, 255, 255));
+    assert!(flat.as_color_ints().iter().all(|&c| c == *Color::rgb(255, 127, 127)));
+}
+
+#[test]
+fn test_scale_integer() {
+    let colors = [
+        Color::rgb(255, 0, 0), Color::rgb(0, 255, 0),
+        Color::rgb(0, 0, 255), Color::argb(128, 10, 20, 30),
+    ];
+    let mut image = Image::solid(2, 2, colors[0]);
+    for (pixel, color) in image.buf.chunks_exact_mut(4).zip(colors) {
+        pixel.copy_from_slice(&color_to_rgba(color));
+    }
+
+    let scaled = image.scale_integer(3);
+    assert_eq!((scaled.width, scaled.height), (6, 6));
+    let pixels = scaled.as_color_ints();
+    for y in 0..6 {
+        for x in 0..6 {
+            assert_eq!(pixels[y * 6 + x], *colors[(y / 3) * 2 + x / 3]);
+        }
+    }
+
+    let restored = scaled.downscale_integer(3);
+    assert_eq!(restored.buf, image.buf);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_par_box_blur_matches_serial() {
+    let mut image = Image::solid(37, 23, Color::rgb(0, 0, 0));
+    let mut seed = 0x2545_f491u32;
+    for value in image.buf.iter_mut() {
+        // xorshift noise
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        *value = seed as u8;
+    }
+
+    for radius in [1, 3, 30] {
+        assert_eq!(image.box_blur(radius).buf, image.par_box_blur(radius).buf);
+    }
+}
+
+#[test]
+fn test_as_color_ints() {
+    let img = Image::from((5, 5, (1..=100).collect::<Vec<u8>>()));
+
+    let colors = img.as_color_ints();
+    assert_eq!(colors.len(), 25);
+    assert_eq!(colors[0], *Color::argb(4, 1, 2, 3));
+    assert_eq!(colors[7], *Color::argb(32, 29, 30, 31));
+    assert_eq!(colors[24], *Color::argb(100, 97, 98, 99));
+
+    // A short buffer yields only its whole pixels, a long one only the first width * height.
+    let short = Image::from((5, 5, (1..=10).collect::<Vec<u8>>()));
+    assert_eq!(short.as_color_ints(), vec![*Color::argb(4, 1, 2, 3), *Color::argb(8, 5, 6, 7)]);
+    let long = Image::from((1, 1, (1..=10).collect::<Vec<u8>>()));
+    assert_eq!(long.as_color_ints(), vec![*Color::argb(4, 1, 2, 3)]);
+}
+
+#[test]
+fn test_from_png_bytes() {
+    let encode = |color: png::ColorType, data: &[u8], palette: Option<Vec<u8>>| {
+        let mut bytes = vec![];
+        let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+        encoder.set_color(color);
+        encoder.set_depth(png::BitDepth::Eight);
+        if let Some(palette) = palette {
+            encoder.set_palette(palette);
+        }
+        encoder.write_header().unwrap().write_image_data(data).unwrap();
+        bytes
+    };
+
+    let gray = Image::from_png_bytes(&encode(png::ColorType::Grayscale, &[10, 200], None)).unwrap();
+    assert_eq!((gray.width, gray.height, gray.format.as_str()), (2, 1, "png"));
+    assert_eq!(gray.buf, vec![10, 10, 10, 255, 200, 200, 200, 255]);
+
+    let palette = vec![255, 0, 0, 0, 0, 255];
+    let indexed = Image::from_png_bytes(&encode(png::ColorType::Indexed, &[1, 0], Some(palette))).unwrap();
+    assert_eq!(indexed.buf, vec![0, 0, 255, 255, 255, 0, 0, 255]);
+
+    let image = Image::checkerboard(3, 2, 1, Color::rgb(1, 2, 3), Color::argb(4, 5, 6, 7));
+    let mut rgba = vec![];
+    image.write_png(&mut rgba).unwrap();
+    assert_eq!(Image::from_png_bytes(&rgba).unwrap().buf, image.buf);
+
+    assert!(matches!(Image::from_png_bytes(b"not a png"), Err(ImageError::Decoding(_))));
+    assert!(matches!(Image::from_png_file("/nonexistent/image.png"), Err(ImageError::Io { kind: std::io::ErrorKind::NotFound, .. })));
+}
+
+#[test]
+fn test_from_jpeg_bytes() {
+    let image = Image::checkerboard(16, 8, 8, Color::rgb(200, 40, 40), Color::rgb(40, 40, 200));
+    let mut bytes = vec![];
+    let encoder = jpeg_encoder::Encoder::new(&mut bytes, 100);
+    encoder.encode(&image.buf, 16, 8, jpeg_encoder::ColorType::Rgba).unwrap();
+
+    let decoded = Image::from_jpeg_bytes(&bytes).unwrap();
+    assert_eq!((decoded.width, decoded.height, decoded.format.as_str()), (16, 8, "jpeg"));
+    assert_eq!(decoded.buf.len(), image.buf.len());
+    assert!(decoded.buf.chunks_exact(4).all(|p| p[3] == 255));
+    for (a, b) in decoded.buf.iter().zip(&image.buf) {
+        assert!(a.abs_diff(*b) <= 8, "{a} vs {b}");
+    }
+
+    assert!(matches!(Image::from_jpeg_bytes(b"not a jpeg"), Err(ImageError::Decoding(_))));
+    assert!(matches!(Image::from_jpeg_bytes(&bytes[..bytes.len() / 2]), Err(ImageError::Decoding(_))));
+    assert!(matches!(Image::from_jpeg_file("/nonexistent/image.jpg"), Err(ImageError::Io { kind: std::io::ErrorKind::NotFound, .. })));
+}
+
+#[test]
+fn test_encode() {
+    let image = Image::checkerboard(16, 8, 8, Color::rgb(200, 40, 40), Color::argb(128, 40, 40, 200));
+
+    let png = image.to_png_bytes().unwrap();
+    let decoded = Image::from_png_bytes(&png).unwrap();
+    assert_eq!((decoded.width, decoded.height), (16, 8));
+    assert_eq!(decoded.buf, image.buf);
+
+    let path = std::env::temp_dir().join(format!("naqsh-test-encode-{}.png", std::process::id()));
+    image.to_png_file(&path).unwrap();
+    assert_eq!(Image::from_png_file(&path).unwrap().buf, image.buf);
+    std::fs::remove_file(&path).unwrap();
+
+    let jpeg = Image::from_jpeg_bytes(&image.to_jpeg_bytes(90).unwrap()).unwrap();
+    assert_eq!((jpeg.width, jpeg.height), (16, 8));
+
+    let mut short = image.clone();
+    short.buf.pop();
+    let expected = ImageError::BufferSize { expected: 512, actual: 511 };
+    assert_eq!(short.to_png_bytes(), Err(expected.clone()));
+    assert_eq!(short.to_jpeg_bytes(90), Err(expected));
+}
+
+#[test]
+fn test_resize() {
+    let image = Image::from((2, 1, vec![0, 0, 0, 0, 200, 100, 40, 255]));
+
+    let nearest = image.resize(4, 2, ResizeFilter::Nearest);
+    assert_eq!((nearest.width, nearest.height), (4, 2));
+    assert_eq!(&nearest.buf[..16], &[0, 0, 0, 0, 0, 0, 0, 0, 200, 100, 40, 255, 200, 100, 40, 255]);
+
+    let bilinear = image.resize(4, 1, ResizeFilter::Bilinear);
+    assert_eq!(bilinear.buf, vec![
+        0, 0, 0, 0,
+        50, 25, 10, 64,
+        150, 75, 30, 191,
+        200, 100, 40, 255,
+    ]);
+
+    let down = image.resize(1, 1, ResizeFilter::Bilinear);
+    assert_eq!(down.buf, vec![100, 50, 20, 128]);
+
+    let empty = Image::new(0, 0, String::new()).resize(3, 2, ResizeFilter::Bilinear);
+    assert_eq!(empty.buf, vec![0; 24]);
+}
+
+#[test]
+fn test_scale_to_fit_area() {
+    let image = Image::solid(1000, 500, Color::rgb(10, 20, 30));
+    let area = 112 * 112;
+
+    let scaled = image.scale_to_fit_area(area);
+    assert!(scaled.width * scaled.height <= area as usize);
+    assert_eq!((scaled.width, scaled.height), (158, 79));
+    assert!(scaled.chunks_exact(4).all(|p| p == [10, 20, 30, 255]));
+
+    let small = Image::solid(100, 50, Color::rgb(10, 20, 30));
+    let unchanged = small.scale_to_fit_area(area);
+    assert_eq!((unchanged.width, unchanged.height), (100, 50));
+    assert_eq!(image.scale_to_fit_area(-1).width, 1000);
+
+    let scaled = image.scale_to_max_dimension(100);
+    assert_eq!((scaled.width, scaled.height), (100, 50));
+    assert_eq!(small.scale_to_max_dimension(100).width, 100);
+}
+
+#[test]
+fn test_crop() {
+    let image = Image::from((3, 3, (0..36).collect::<Vec<u8>>()));
+
+    let cropped = image.crop(&Rect::new(1, 1, 3, 3));
+    assert_eq!((cropped.width, cropped.height), (2, 2));
+    assert_eq!(cropped.buf, vec![16, 17, 18, 19, 20, 21, 22, 23, 28, 29, 30, 31, 32, 33, 34, 35]);
+
+    let clamped = image.crop(&Rect::new(-5, 2, 10, 10));
+    assert_eq!((clamped.width, clamped.height), (3, 1));
+    assert_eq!(clamped.buf, (24..36).collect::<Vec<u8>>());
+
+    for region in [Rect::new(2, 2, 1, 1), Rect::new(1, 1, 1, 3), Rect::new(5, 5, 9, 9)] {
+        let empty = image.crop(&region);
+        assert_eq!((empty.width, empty.height, empty.buf.len()), (0, 0, 0));
+    }
+}
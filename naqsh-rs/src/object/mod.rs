@@ -1,3 +1,3 @@
 mod rect;
 
-pub use rect::Rect;
\ No newline at end of file
+pub use rect::{BoundingBox, Rect};
\ No newline at end of file
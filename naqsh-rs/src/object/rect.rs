@@ -1,3 +1,4 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Rect {
     pub left: i32,
     pub top: i32,
@@ -79,6 +80,24 @@ impl Rect {
         self.top = new_top;
     }
 
+    /// Moves the left and right sides `dx` towards the center and the top and bottom sides `dy`
+    /// towards the center. Negative values grow the rectangle. Insetting by more than half the
+    /// width or height makes the sides cross, so the rectangle becomes empty and invalid.
+    pub fn inset(&mut self, dx: i32, dy: i32) {
+        self.left += dx;
+        self.top += dy;
+        self.right -= dx;
+        self.bottom -= dy;
+    }
+
+    /// Clips the rectangle to `bounds`. If they do not intersect the rectangle becomes empty,
+    /// with `right` and `bottom` pulled back to no less than `left` and `top`.
+    pub fn clamp_to(&mut self, bounds: &Rect) {
+        self.intersect_unchecked(bounds);
+        self.right = self.right.max(self.left);
+        self.bottom = self.bottom.max(self.top);
+    }
+
     pub fn intersect(&mut self, left: i32, top: i32, right: i32, bottom: i32) -> bool {
         if self.left < right && left < self.right && self.top < bottom && top < self.bottom {
             if self.left < left {self.left = left;}
@@ -90,17 +109,61 @@ impl Rect {
         false
     }
 
-    pub fn intersect_unchecked(&mut self, other: Rect) {
+    pub fn intersect_unchecked(&mut self, other: &Rect) {
         self.left = i32::max(self.left, other.left);
         self.top = i32::max(self.top, other.top);
         self.right = i32::min(self.right, other.right);
         self.bottom = i32::min(self.bottom, other.bottom);
     }
 
+    /// Expands the rectangle to contain the rectangle given by `left`, `top`, `right` and
+    /// `bottom`. Nothing happens if that rectangle is empty, and an empty rectangle takes on its
+    /// bounds.
+    pub fn union(&mut self, left: i32, top: i32, right: i32, bottom: i32) {
+        if left < right && top < bottom {
+            self.union_unchecked(&Rect::new(left, top, right, bottom));
+        }
+    }
+
+    /// Expands the rectangle to contain `other` without checking it for emptiness, so an
+    /// empty `other` still stretches the bounds towards its coordinates. An empty rectangle
+    /// takes on the bounds of `other`.
+    pub fn union_unchecked(&mut self, other: &Rect) {
+        if self.is_empty() {
+            self.set(other.left, other.top, other.right, other.bottom);
+            return;
+        }
+        self.left = i32::min(self.left, other.left);
+        self.top = i32::min(self.top, other.top);
+        self.right = i32::max(self.right, other.right);
+        self.bottom = i32::max(self.bottom, other.bottom);
+    }
+
     pub fn intersects(&self, left: i32, top: i32, right: i32, bottom: i32) -> bool {
         self.left < right && left < self.right && self.top < bottom && top < self.bottom
     }
 
+    /// Returns `true` if `(x, y)` is inside the rectangle. `left` and `top` are inclusive while
+    /// `right` and `bottom` are exclusive, so an empty rectangle contains no point.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        self.left <= x && x < self.right && self.top <= y && y < self.bottom
+    }
+
+    /// Returns `true` if `other` is inside of or equal to the rectangle. An empty rectangle
+    /// contains no other rectangle.
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        !self.is_empty()
+            && self.left <= other.left && self.top <= other.top
+            && self.right >= other.right && self.bottom >= other.bottom
+    }
+
+    /// Returns every `(x, y)` inside the rectangle in row-major order. `right` and `bottom` are
+    /// exclusive, so an empty rectangle yields nothing.
+    pub fn points(&self) -> impl Iterator<Item = (i32, i32)> {
+        let (left, right) = (self.left, self.right);
+        (self.top..self.bottom).flat_map(move |y| (left..right).map(move |x| (x, y)))
+    }
+
     pub fn scale(&mut self, scale: f32) {
         if scale != 1.0_f32 {
             self.left = (self.left as f32 * scale + 0.5_f32) as i32;
@@ -122,4 +185,141 @@ impl Default for Rect {
             bottom: Default::default()
         }
     }
-}
\ No newline at end of file
+}
+
+/// Accumulates the smallest rectangle containing every rectangle given to
+/// [include](BoundingBox::include). Empty rectangles are ignored.
+#[derive(Default)]
+pub struct BoundingBox {
+    bounds: Option<Rect>,
+}
+
+impl BoundingBox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Grows the bounding box to contain `rect`.
+    pub fn include(&mut self, rect: &Rect) {
+        if rect.is_empty() {
+            return;
+        }
+        self.bounds = Some(match self.bounds.take() {
+            None => *rect,
+            Some(bounds) => Rect::new(
+                bounds.left.min(rect.left),
+                bounds.top.min(rect.top),
+                bounds.right.max(rect.right),
+                bounds.bottom.max(rect.bottom),
+            ),
+        });
+    }
+
+    /// Returns the bounding box, or `None` if no non-empty rectangle was included.
+    pub fn finish(self) -> Option<Rect> {
+        self.bounds
+    }
+}
+
+#[test]
+fn test_points() {
+    let rect = Rect::new(2, 3, 5, 5);
+    let points: Vec<(i32, i32)> = rect.points().collect();
+    assert_eq!(points.len() as i32, rect.width() * rect.height());
+    assert_eq!(points.first(), Some(&(2, 3)));
+    assert_eq!(points.last(), Some(&(4, 4)));
+    assert!(points.contains(&(4, 3)) && points.contains(&(2, 4)));
+    assert_eq!(Rect::new(0, 0, 0, 4).points().count(), 0);
+}
+
+
+#[test]
+fn test_bounding_box() {
+    assert!(BoundingBox::new().finish().is_none());
+
+    let mut bounds = BoundingBox::new();
+    bounds.include(&Rect::new(0, 0, 0, 0));
+
+    bounds.include(&Rect::new(10, 20, 30, 40));
+    bounds.include(&Rect::new(-5, 25, 15, 35));
+    bounds.include(&Rect::new(100, 100, 100, 200));
+    bounds.include(&Rect::new(20, 0, 25, 50));
+    let rect = bounds.finish().unwrap();
+    assert_eq!((rect.left, rect.top, rect.right, rect.bottom), (-5, 0, 30, 50));
+}
+
+#[test]
+fn test_contains() {
+    let rect = Rect::new(2, 3, 5, 6);
+    assert!(rect.contains(2, 3));
+    assert!(rect.contains(4, 5));
+    assert!(!rect.contains(5, 5));
+    assert!(!rect.contains(4, 6));
+    assert!(!rect.contains(1, 4));
+    assert!(!Rect::new(2, 2, 2, 5).contains(2, 3));
+
+    assert!(rect.contains_rect(&Rect::new(2, 3, 5, 6)));
+    assert!(rect.contains_rect(&Rect::new(3, 4, 4, 5)));
+    assert!(!rect.contains_rect(&Rect::new(3, 4, 6, 5)));
+    assert!(!rect.contains_rect(&Rect::new(1, 3, 4, 5)));
+    assert!(!Rect::default().contains_rect(&Rect::default()));
+}
+
+#[test]
+fn test_union() {
+    let coordinates = |rect: Rect| (rect.left, rect.top, rect.right, rect.bottom);
+
+    let mut overlapping = Rect::new(0, 0, 10, 10);
+    overlapping.union(5, 5, 15, 12);
+    assert_eq!(coordinates(overlapping), (0, 0, 15, 12));
+
+    let mut disjoint = Rect::new(0, 0, 2, 2);
+    disjoint.union(8, -4, 9, -1);
+    assert_eq!(coordinates(disjoint), (0, -4, 9, 2));
+
+    let mut empty = Rect::default();
+    empty.union(5, 6, 7, 8);
+    assert_eq!(coordinates(empty), (5, 6, 7, 8));
+    let mut rect = Rect::new(1, 1, 3, 3);
+    rect.union(10, 10, 10, 20);
+    assert_eq!(coordinates(rect), (1, 1, 3, 3));
+
+    let mut unchecked = Rect::new(1, 1, 3, 3);
+    unchecked.union_unchecked(&Rect::new(10, 10, 10, 20));
+    assert_eq!(coordinates(unchecked), (1, 1, 10, 20));
+    let mut empty = Rect::default();
+    empty.union_unchecked(&Rect::new(5, 6, 7, 8));
+    assert_eq!(coordinates(empty), (5, 6, 7, 8));
+}
+
+#[test]
+fn test_inset_and_clamp_to() {
+    let coordinates = |rect: &Rect| (rect.left, rect.top, rect.right, rect.bottom);
+
+    let mut rect = Rect::new(0, 0, 10, 8);
+    rect.inset(2, 1);
+    assert_eq!(coordinates(&rect), (2, 1, 8, 7));
+    rect.inset(-3, -1);
+    assert_eq!(coordinates(&rect), (-1, 0, 11, 8));
+    rect.inset(7, 0);
+    assert!(rect.is_empty() && !rect.is_valid());
+
+    let bounds = Rect::new(0, 0, 10, 10);
+    let mut rect = Rect::new(-5, 4, 6, 20);
+    rect.clamp_to(&bounds);
+    assert_eq!(coordinates(&rect), (0, 4, 6, 10));
+    let mut outside = Rect::new(12, 2, 15, 4);
+    outside.clamp_to(&bounds);
+    assert!(outside.is_empty() && outside.is_valid());
+}
+
+#[test]
+fn test_copy_and_compare() {
+    let rect = Rect::new(0, 0, 10, 10);
+    let mut copy = rect;
+    copy.intersect_unchecked(&Rect::new(5, -5, 15, 5));
+    assert_eq!(rect, Rect::new(0, 0, 10, 10));
+    assert_eq!(copy, Rect::new(5, 0, 10, 5));
+    assert_ne!(copy, rect);
+    assert_eq!(format!("{:?}", copy), "Rect { left: 5, top: 0, right: 10, bottom: 5 }");
+}
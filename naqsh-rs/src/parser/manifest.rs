@@ -1,22 +1,217 @@
-use std::path::PathBuf;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 
-use crate::graphics::ColorInt;
+use crate::graphics::{Color, ColorInt};
 
 #[derive(Debug, Deserialize)]
+#[serde(default)]
 /// The representation of the Manifest file.
+///
+/// Every field is optional; missing fields take their value from [Manifest::default].
 pub struct Manifest {
-    /// The format of the generated Image.
+    /// The format of the generated Image. Defaults to `"png"`.
     pub format: String,
-    /// Size of the generated Image.
+    /// Size of the generated Image. Defaults to [Manifest::DEFAULT_SIZE].
     pub size: (usize, usize),
-    /// Background Color of the generated Image.
+    /// Background Color of the generated Image. Defaults to transparent.
     pub color: ColorInt,
     /// Assets used during generation of the Image.
     pub assets: Option<Vec<Asset>>,
-    /// Objects in the generated Image.
+    /// Objects in the generated Image. Defaults to none.
     pub objects: Vec<Object>,
+    /// What to do when an Object's `src` or `asset` can not be loaded. Defaults to `"error"`.
+    pub on_missing: Option<MissingAssetPolicy>,
+    /// Places the Objects into the cells of a grid, in order, instead of at their own
+    /// coordinates.
+    pub layout: Option<GridSpec>,
+}
+
+impl Manifest {
+    /// Canvas size used when a manifest does not specify one.
+    pub const DEFAULT_SIZE: (usize, usize) = (512, 512);
+
+    /// Deserializes a Manifest from any serde `deserializer`. Errors carry the path of the field
+    /// that failed, such as `objects[2].color`.
+    pub fn from_deserializer<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Manifest, ManifestError> {
+        serde_path_to_error::deserialize(deserializer).map_err(|err| ManifestError::Invalid {
+            path: err.path().to_string(),
+            message: err.inner().to_string(),
+        })
+    }
+
+    /// Parses a Manifest from a TOML document.
+    pub fn from_toml_str(toml: &str) -> Result<Manifest, ManifestError> {
+        Self::from_deserializer(toml::Deserializer::new(toml))
+    }
+
+    /// Reads and parses the TOML Manifest at `path`.
+    pub fn from_toml_file<P: AsRef<Path>>(path: P) -> Result<Manifest, ManifestError> {
+        Self::from_toml_str(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses a Manifest from a JSON document.
+    pub fn from_json_str(json: &str) -> Result<Manifest, ManifestError> {
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        let manifest = Self::from_deserializer(&mut deserializer)?;
+        // Reject trailing characters after the document
+        deserializer.end().map_err(|err| ManifestError::Invalid {
+            path: String::from("."),
+            message: err.to_string(),
+        })?;
+        Ok(manifest)
+    }
+
+    /// Reads and parses the JSON Manifest at `path`.
+    pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<Manifest, ManifestError> {
+        Self::from_json_str(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses a Manifest from a YAML document.
+    pub fn from_yaml_str(yaml: &str) -> Result<Manifest, ManifestError> {
+        Self::from_deserializer(serde_yaml::Deserializer::from_str(yaml))
+    }
+
+    /// Reads and parses the YAML Manifest at `path`.
+    pub fn from_yaml_file<P: AsRef<Path>>(path: P) -> Result<Manifest, ManifestError> {
+        Self::from_yaml_str(&std::fs::read_to_string(path)?)
+    }
+
+    /// Checks the Manifest for mistakes its types can not rule out, returning every problem
+    /// found rather than only the first:
+    ///
+    /// - `size` must have a nonzero width and height.
+    /// - Every Object `asset` must be the id of one of the `assets`.
+    /// - Every Object needs one of `src`, `asset`, `text`, `color` or `rgba`.
+    /// - `size` percentages must be above 0% and at most 100%, coordinate percentages within
+    ///   0% and 100%.
+    ///
+    /// A [Parser](super::Parser) given extra assets can resolve ids the Manifest does not
+    /// declare, so it does not call this itself.
+    pub fn validate(&self) -> Result<(), Vec<ManifestError>> {
+        let mut errors = vec![];
+        let mut invalid = |path: String, message: &str| {
+            errors.push(ManifestError::Invalid { path, message: message.to_string() });
+        };
+
+        if self.size.0 == 0 || self.size.1 == 0 {
+            invalid(String::from("size"), "width and height must be nonzero");
+        }
+        let asset_ids: Vec<&str> = self.assets.iter().flatten().map(|asset| asset.id.as_str()).collect();
+        for (index, object) in self.objects.iter().enumerate() {
+            let path = |field: &str| format!("objects[{index}].{field}");
+            if let Some(id) = &object.asset {
+                if !asset_ids.contains(&id.as_str()) {
+                    invalid(path("asset"), &format!("no asset has the id `{id}`"));
+                }
+            }
+            if object.src.is_none() && object.asset.is_none() && object.text.is_none()
+                && object.color.is_none() && object.rgba.is_none() {
+                invalid(format!("objects[{index}]"), "needs one of `src`, `asset`, `text`, `color` or `rgba`");
+            }
+            if let Some((x, y)) = object.size {
+                if [x, y].into_iter().flatten().any(|percent| !(percent > 0f64 && percent <= 100f64)) {
+                    invalid(path("size"), "percentages must be above 0% and at most 100%");
+                }
+            }
+            if let Some((left, top, right, bottom)) = object.coordinates {
+                let out_of_range = [left, top, right, bottom].into_iter().any(|dimension| {
+                    matches!(dimension, Dimension::Percent(percent) if !(0f64..=100f64).contains(&percent))
+                });
+                if out_of_range {
+                    invalid(path("coordinates"), "percentages must be within 0% and 100%");
+                }
+            }
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Reads and parses the Manifest at `path`, choosing the format from its extension:
+    /// `.toml`, `.json`, `.yaml` or `.yml`. Case is ignored.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Manifest, ManifestError> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+        match extension.to_ascii_lowercase().as_str() {
+            "toml" => Self::from_toml_file(path),
+            "json" => Self::from_json_file(path),
+            "yaml" | "yml" => Self::from_yaml_file(path),
+            _ => Err(ManifestError::UnknownFormat(path.display().to_string())),
+        }
+    }
+}
+
+/// Errors returned while loading a [Manifest].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManifestError {
+    /// The Manifest file could not be read, with the kind and message of the underlying
+    /// [io::Error](std::io::Error).
+    Io { kind: std::io::ErrorKind, message: String },
+    /// The extension of the Manifest file at this path is not a supported format.
+    UnknownFormat(String),
+    /// The field at `path`, such as `objects[2].color`, is invalid.
+    Invalid { path: String, message: String },
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestError::Io { message, .. } => write!(f, "failed to read manifest: {message}"),
+            ManifestError::UnknownFormat(path) => {
+                write!(f, "unknown manifest format of `{path}`, expected .toml, .json, .yaml or .yml")
+            }
+            ManifestError::Invalid { path, message } => write!(f, "invalid manifest at `{path}`: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+impl From<std::io::Error> for ManifestError {
+    fn from(err: std::io::Error) -> Self {
+        ManifestError::Io { kind: err.kind(), message: err.to_string() }
+    }
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self {
+            format: String::from("png"),
+            size: Self::DEFAULT_SIZE,
+            color: Color::TRANSPARENT,
+            assets: None,
+            objects: vec![],
+            on_missing: None,
+            layout: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+/// A grid of equally sized cells covering the generated Image.
+pub struct GridSpec {
+    /// Number of rows.
+    pub rows: usize,
+    /// Number of columns.
+    pub cols: usize,
+    /// Space between neighbouring cells in pixels. Defaults to `0`.
+    #[serde(default)]
+    pub gap: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Policy applied to an Object whose image can not be loaded.
+pub enum MissingAssetPolicy {
+    /// Fail the whole render.
+    #[default]
+    Error,
+    /// Leave the Object out of the generated Image.
+    Skip,
+    /// Draw a magenta checkerboard in place of the Object.
+    Placeholder,
 }
 
 #[derive(Debug, Deserialize)]
@@ -41,11 +236,251 @@ pub struct Object {
     pub color: Option<ColorInt>,
     /// Color of Object in rgba.
     pub rgba: Option<(u8, u8, u8, u8)>,
+    /// Name of a palette target (e.g. `"vibrant"`) used to recolor the Object.
+    /// The palette is generated from the asset referenced by `asset`, and the Object's image
+    /// (`src`, or the asset itself if `src` is not set) is tinted with the selected swatch.
+    pub recolor_from_palette: Option<String>,
     /// Text Content of Object.
     pub text: Option<String>,
+    /// Path of the font file `text` is set in.
+    pub font: Option<PathBuf>,
+    /// Size of `text` in pixels per em. Defaults to `16`.
+    pub font_size: Option<f32>,
     /// Resize Object to coordinates with x and y values represented as percentages of width and height of Image respectively.
     /// If either of x value, y value is null, aspect-ratio of Object is maintained.
     pub size: Option<(Option<f64>, Option<f64>)>,
     /// The left, top, right, bottom coordinate of the Object. If size is specified, only the left and top coordinate is used.
-    pub coordinates: Option<(f64, f64, f64, f64)>
-}
\ No newline at end of file
+    /// Otherwise the Object is scaled into this box according to `scale_mode`. Parts outside of the Image are clipped.
+    /// Each coordinate is either a number of pixels (`10`, `"10px"`) or a percentage of the width or height of
+    /// Image (`"50%"`).
+    pub coordinates: Option<(Dimension, Dimension, Dimension, Dimension)>,
+    /// How the Object's image is mapped into its target area. Defaults to `"fit"`.
+    pub scale_mode: Option<ScaleMode>,
+    /// Drop shadow drawn beneath the Object.
+    pub shadow: Option<ShadowSpec>,
+    /// Name of another Object this Object is placed relative to. The Object's coordinates are
+    /// then taken from the top-left corner of the other Object; without coordinates it covers
+    /// the same area.
+    pub relative_to: Option<String>,
+    /// Offset in pixels added to the position of an Object placed with `relative_to`.
+    /// Defaults to `(0, 0)`.
+    pub offset: Option<(i32, i32)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+/// A blurred, offset copy of an Object drawn beneath it.
+pub struct ShadowSpec {
+    /// Color of the shadow. Its alpha scales the opacity of the shadow.
+    pub color: ColorInt,
+    /// Offset of the shadow from the Object in pixels. Defaults to `(0, 0)`.
+    #[serde(default)]
+    pub offset: (i32, i32),
+    /// Radius of the box blur applied to the shadow in pixels. Defaults to `0`.
+    #[serde(default)]
+    pub blur: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "RawDimension")]
+/// A length along one axis of the generated Image.
+pub enum Dimension {
+    /// An absolute number of pixels.
+    Px(i32),
+    /// A percentage of the width or height of the Image.
+    Percent(f64),
+}
+
+impl Dimension {
+    /// Returns this dimension in pixels, with percentages taken of `length`.
+    pub fn resolve(&self, length: usize) -> i32 {
+        match *self {
+            Dimension::Px(px) => px,
+            Dimension::Percent(percent) => (length as f64 * percent / 100f64).round() as i32,
+        }
+    }
+}
+
+/// Error returned when a string is not a valid [Dimension].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidDimension(pub String);
+
+impl fmt::Display for InvalidDimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid dimension `{}`, expected pixels such as `10` or a percentage such as `50%`", self.0)
+    }
+}
+
+impl std::error::Error for InvalidDimension {}
+
+impl FromStr for Dimension {
+    type Err = InvalidDimension;
+
+    /// Parses `"50%"` as a percentage and `"10px"` or `"10"` as pixels.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let invalid = || InvalidDimension(s.to_string());
+        if let Some(percent) = trimmed.strip_suffix('%') {
+            return percent.trim().parse().map(Dimension::Percent).map_err(|_| invalid());
+        }
+        let px = trimmed.strip_suffix("px").unwrap_or(trimmed).trim();
+        px.parse().map(Dimension::Px).map_err(|_| invalid())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+/// Forms a [Dimension] can be written in.
+enum RawDimension {
+    Px(i32),
+    Text(String),
+}
+
+impl TryFrom<RawDimension> for Dimension {
+    type Error = InvalidDimension;
+
+    fn try_from(raw: RawDimension) -> Result<Self, Self::Error> {
+        match raw {
+            RawDimension::Px(px) => Ok(Dimension::Px(px)),
+            RawDimension::Text(text) => text.parse(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// How an image is mapped into an area with a different aspect-ratio.
+pub enum ScaleMode {
+    /// Scale to fit inside the area, preserving aspect-ratio. Uncovered parts are left empty.
+    #[default]
+    Fit,
+    /// Scale to cover the whole area, preserving aspect-ratio. Overflowing parts are cropped.
+    Fill,
+    /// Scale each axis independently to cover the area exactly.
+    Stretch,
+}
+
+#[test]
+fn test_partial_manifest_defaults() {
+    let manifest: Manifest = toml::from_str(r#"
+        [[objects]]
+        name = "logo"
+        src = "logo.png"
+    "#).unwrap();
+
+    assert_eq!(manifest.format, "png");
+    assert_eq!(manifest.size, Manifest::DEFAULT_SIZE);
+    assert_eq!(manifest.color, Color::TRANSPARENT);
+    assert!(manifest.assets.is_none());
+    assert_eq!(manifest.objects.len(), 1);
+    assert_eq!(manifest.objects[0].name, "logo");
+
+    let empty: Manifest = toml::from_str("").unwrap();
+    assert!(empty.objects.is_empty());
+    assert_eq!(empty.size, Manifest::default().size);
+}
+
+#[test]
+fn test_manifest_error_path() {
+    let toml = r#"
+        [[objects]]
+        name = "a"
+        color = 0
+
+        [[objects]]
+        name = "b"
+
+        [[objects]]
+        name = "c"
+        color = "not a color"
+    "#;
+    let err = Manifest::from_deserializer(toml::Deserializer::new(toml)).unwrap_err();
+    let ManifestError::Invalid { path, .. } = &err else {
+        panic!("expected an invalid field, got {err}");
+    };
+    assert_eq!(path, "objects[2].color");
+    assert!(err.to_string().contains("objects[2]"));
+}
+
+#[test]
+fn test_from_toml() {
+    let manifest = Manifest::from_toml_str(r#"
+        size = [64, 32]
+        color = -16777216
+
+        [[objects]]
+        name = "title"
+        text = "naqsh"
+    "#).unwrap();
+    assert_eq!(manifest.size, (64, 32));
+    assert_eq!(manifest.color, Color::BLACK);
+    assert_eq!(manifest.objects[0].text.as_deref(), Some("naqsh"));
+
+    assert!(matches!(Manifest::from_toml_str("size = [64"), Err(ManifestError::Invalid { .. })));
+
+    let path = std::env::temp_dir().join(format!("naqsh-manifest-{}.toml", std::process::id()));
+    std::fs::write(&path, "format = \"jpeg\"").unwrap();
+    assert_eq!(Manifest::from_toml_file(&path).unwrap().format, "jpeg");
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(Manifest::from_toml_file(&path), Err(ManifestError::Io { kind: std::io::ErrorKind::NotFound, .. })));
+}
+
+#[test]
+fn test_from_json_and_yaml() {
+    let json = Manifest::from_json_str(r#"{
+        "size": [64, 32],
+        "objects": [{ "name": "logo", "size": [50.0, null] }]
+    }"#).unwrap();
+    assert_eq!(json.size, (64, 32));
+    assert_eq!(json.objects[0].size, Some((Some(50.0), None)));
+    assert!(Manifest::from_json_str("{} []").is_err());
+
+    let yaml = Manifest::from_yaml_str("size: [64, 32]\nobjects:\n  - name: logo\n    size: [~, 25.0]\n").unwrap();
+    assert_eq!(yaml.size, (64, 32));
+    assert_eq!(yaml.objects[0].size, Some((None, Some(25.0))));
+
+    let err = Manifest::from_yaml_str("objects:\n  - name: logo\n    color: red\n").unwrap_err();
+    assert!(matches!(err, ManifestError::Invalid { ref path, .. } if path == "objects[0].color"));
+
+    let dir = std::env::temp_dir().join(format!("naqsh-manifests-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for (name, content) in [("a.toml", "format = \"jpeg\""), ("b.JSON", r#"{"format": "jpeg"}"#), ("c.yml", "format: jpeg")] {
+        std::fs::write(dir.join(name), content).unwrap();
+        assert_eq!(Manifest::from_path(dir.join(name)).unwrap().format, "jpeg");
+    }
+    assert!(matches!(Manifest::from_path(dir.join("d.ini")), Err(ManifestError::UnknownFormat(_))));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_validate() {
+    let valid = Manifest::from_toml_str(r#"
+        assets = [{ id = "logo", src = "logo.png" }]
+
+        [[objects]]
+        name = "logo"
+        asset = "logo"
+        size = [50.0, 100.0]
+        coordinates = ["0%", 0, "100%", 10]
+    "#).unwrap();
+    assert_eq!(valid.validate(), Ok(()));
+
+    let invalid = Manifest::from_toml_str(r#"
+        size = [0, 10]
+
+        [[objects]]
+        name = "ghost"
+        asset = "missing"
+        size = [0.0, 150.0]
+
+        [[objects]]
+        name = "empty"
+        coordinates = ["-5%", 0, 10, 10]
+    "#).unwrap();
+    let paths: Vec<String> = invalid.validate().unwrap_err().into_iter()
+        .map(|err| match err {
+            ManifestError::Invalid { path, .. } => path,
+            err => panic!("unexpected error {err}"),
+        })
+        .collect();
+    assert_eq!(paths, vec!["size", "objects[0].asset", "objects[0].size", "objects[1]", "objects[1].coordinates"]);
+}
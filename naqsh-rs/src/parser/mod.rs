@@ -2,4 +2,4 @@ mod manifest;
 mod parser;
 
 pub use manifest::*;
-pub use parser::Parser;
\ No newline at end of file
+pub use parser::{ParseError, Parser};
\ No newline at end of file
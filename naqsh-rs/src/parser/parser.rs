@@ -1,22 +1,790 @@
 #![allow(dead_code, unused_variables)]
-use crate::image::Image;
-use super::Manifest;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::asset::{Assets, AssetsMethods};
+use crate::font::Font;
+use crate::graphics::{Canvas, Color, ColorInt, Draw, PaletteBuilder, PaletteError, Target, Text};
+use crate::image::{Image, ResizeFilter};
+use crate::object::Rect;
+use super::{GridSpec, Manifest, MissingAssetPolicy, Object, ScaleMode, ShadowSpec};
+
+/// Errors returned while generating an [Image] from a [Manifest].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The image of the named Object could not be loaded.
+    MissingAsset(String),
+    /// An Object is placed relative to an Object name that does not exist.
+    UnknownObject(String),
+    /// An Object refers to an Asset id that does not exist.
+    UnknownAsset(String),
+    /// The named Object is, directly or indirectly, placed relative to itself.
+    PlacementCycle(String),
+    /// The coordinates of the named Object have a right or bottom edge before its left or top.
+    InvalidCoordinates(String),
+    /// The named Object has `text` but no `font`, or its font could not be loaded.
+    MissingFont(String),
+    /// The `recolor_from_palette` of the named Object is not the name of a palette target.
+    UnknownTarget(String),
+    /// The palette recoloring `object` could not be generated.
+    Palette { object: String, error: PaletteError },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingAsset(name) => write!(f, "could not load the image of object `{name}`"),
+            ParseError::UnknownObject(name) => write!(f, "no object is called `{name}`"),
+            ParseError::UnknownAsset(id) => write!(f, "no asset has the id `{id}`"),
+            ParseError::PlacementCycle(name) => write!(f, "object `{name}` is placed relative to itself"),
+            ParseError::InvalidCoordinates(name) => write!(f, "object `{name}` has invalid coordinates"),
+            ParseError::MissingFont(name) => write!(f, "could not load the font of object `{name}`"),
+            ParseError::UnknownTarget(name) => write!(f, "object `{name}` is recolored from an unknown palette target"),
+            ParseError::Palette { object, error } => {
+                write!(f, "could not generate the palette of object `{object}`: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl MissingAssetPolicy {
+    const PLACEHOLDER_CELL_SIZE: usize = 8;
+
+    /// Applies this policy to Object `name`, whose `width` x `height` image could not be loaded.
+    ///
+    /// Returns the image to draw instead, `None` if the Object should be skipped, or an error.
+    pub(crate) fn apply(&self, name: &str, width: usize, height: usize) -> Result<Option<Image>, ParseError> {
+        match self {
+            MissingAssetPolicy::Error => Err(ParseError::MissingAsset(name.to_string())),
+            MissingAssetPolicy::Skip => Ok(None),
+            MissingAssetPolicy::Placeholder => Ok(Some(Image::checkerboard(
+                width, height, Self::PLACEHOLDER_CELL_SIZE, Color::MAGENTA, Color::BLACK
+            ))),
+        }
+    }
+}
+
+impl ScaleMode {
+    /// Maps a `width` x `height` image into `area`.
+    ///
+    /// Returns the region of the image to draw and the rectangle it is drawn to.
+    pub fn place(&self, width: usize, height: usize, area: &Rect) -> (Rect, Rect) {
+        let source = Rect::new(0, 0, width as i32, height as i32);
+        if width == 0 || height == 0 || area.is_empty() {
+            return (source, Rect::new(area.left, area.top, area.left, area.top));
+        }
+
+        let scale_x = area.width() as f64 / width as f64;
+        let scale_y = area.height() as f64 / height as f64;
+        match self {
+            ScaleMode::Stretch => (source, *area),
+            ScaleMode::Fit => {
+                let scale = f64::min(scale_x, scale_y);
+                let w = (width as f64 * scale).round() as i32;
+                let h = (height as f64 * scale).round() as i32;
+                let left = area.left + (area.width() - w) / 2;
+                let top = area.top + (area.height() - h) / 2;
+                (source, Rect::new(left, top, left + w, top + h))
+            }
+            ScaleMode::Fill => {
+                let scale = f64::max(scale_x, scale_y);
+                let w = (area.width() as f64 / scale).round() as i32;
+                let h = (area.height() as f64 / scale).round() as i32;
+                let left = (width as i32 - w) / 2;
+                let top = (height as i32 - h) / 2;
+                (Rect::new(left, top, left + w, top + h), *area)
+            }
+        }
+    }
+}
+
+impl ShadowSpec {
+    /// Returns `object` with this shadow drawn beneath it, on an image grown to fit the shadow,
+    /// together with the position of `object` inside that image.
+    pub(crate) fn render(&self, object: &Image) -> (Image, (i32, i32)) {
+        let (dx, dy) = self.offset;
+        let pad = self.blur as i32;
+        let width = object.width as i32 + dx.abs() + 2 * pad;
+        let height = object.height as i32 + dy.abs() + 2 * pad;
+        let position = (pad + (-dx).max(0), pad + (-dy).max(0));
+        let area = Rect::new(0, 0, object.width as i32, object.height as i32);
+
+        let mut out = Image::new(width as usize, height as usize, String::new());
+        out.blend_region(object, &area, (position.0 + dx, position.1 + dy));
+        // Tint after blurring, so the edges fade out in the shadow color rather than towards
+        // the transparent black around it
+        let mut out = out.box_blur(self.blur);
+        out.tint(self.color);
+        out.blend_region(object, &area, position);
+        (out, position)
+    }
+}
+
+impl GridSpec {
+    /// Returns the rectangle of cell `index`, counting row by row, in a `width` x `height`
+    /// Image, or `None` if the grid has fewer cells.
+    pub fn cell(&self, index: usize, width: usize, height: usize) -> Option<Rect> {
+        if index >= self.rows * self.cols {
+            return None;
+        }
+        let cell_width = width.saturating_sub(self.gap * (self.cols - 1)) / self.cols;
+        let cell_height = height.saturating_sub(self.gap * (self.rows - 1)) / self.rows;
+        let left = (index % self.cols * (cell_width + self.gap)) as i32;
+        let top = (index / self.cols * (cell_height + self.gap)) as i32;
+        Some(Rect::new(left, top, left + cell_width as i32, top + cell_height as i32))
+    }
+}
+
+impl Manifest {
+    /// Returns the rectangle Object `index` is placed in: its grid cell if the Manifest has a
+    /// `layout`, otherwise its resolved `coordinates`.
+    pub(crate) fn object_rect(&self, index: usize) -> Option<Rect> {
+        let (width, height) = self.size;
+        match &self.layout {
+            Some(grid) => grid.cell(index, width, height),
+            None => self.objects.get(index)?.resolve_coordinates(width, height),
+        }
+    }
+
+    /// Returns the rectangle every Object is placed in. Objects with `relative_to` are placed
+    /// after the Object they refer to; the others are placed as by [Manifest::object_rect].
+    ///
+    /// Returns [ParseError::UnknownObject] for a reference to a missing Object and
+    /// [ParseError::PlacementCycle] if Objects refer to each other in a cycle.
+    pub(crate) fn object_rects(&self) -> Result<Vec<Option<Rect>>, ParseError> {
+        let mut names = HashMap::new();
+        for (index, object) in self.objects.iter().enumerate() {
+            names.entry(object.name.as_str()).or_insert(index);
+        }
+        let mut placements = vec![Placement::Pending; self.objects.len()];
+        (0..self.objects.len())
+            .map(|index| self.place_object(index, &names, &mut placements))
+            .collect()
+    }
+
+    fn place_object(
+        &self,
+        index: usize,
+        names: &HashMap<&str, usize>,
+        placements: &mut [Placement]
+    ) -> Result<Option<Rect>, ParseError> {
+        let object = &self.objects[index];
+        match placements[index] {
+            Placement::Placed(rect) => return Ok(rect.map(|(l, t, r, b)| Rect::new(l, t, r, b))),
+            Placement::InProgress => return Err(ParseError::PlacementCycle(object.name.clone())),
+            Placement::Pending => {}
+        }
+
+        let rect = match &object.relative_to {
+            None => self.object_rect(index),
+            Some(name) => {
+                let &base_index = names.get(name.as_str())
+                    .ok_or_else(|| ParseError::UnknownObject(name.clone()))?;
+                placements[index] = Placement::InProgress;
+                self.place_object(base_index, names, placements)?.map(|base| {
+                    let (width, height) = self.size;
+                    let mut rect = object.resolve_coordinates(width, height)
+                        .unwrap_or(Rect::new(0, 0, base.width(), base.height()));
+                    let (dx, dy) = object.offset.unwrap_or_default();
+                    rect.offset(base.left + dx, base.top + dy);
+                    rect
+                })
+            }
+        };
+        placements[index] = Placement::Placed(rect.as_ref().map(|r| (r.left, r.top, r.right, r.bottom)));
+        Ok(rect)
+    }
+}
+
+/// Placement state of an Object while resolving relative positions.
+#[derive(Clone, Copy)]
+enum Placement {
+    Pending,
+    InProgress,
+    Placed(Option<(i32, i32, i32, i32)>),
+}
+
+impl Object {
+    const DEFAULT_FONT_SIZE: f32 = 16f32;
+
+    /// Returns the Color given by `color` or `rgba`, if any.
+    pub(crate) fn fill_color(&self) -> Option<ColorInt> {
+        self.color.or(self.rgba.map(|(r, g, b, a)| Color::argb(a, r, g, b)))
+    }
+
+    /// Resolves `coordinates` against a `width` x `height` Image into the rectangle covered by
+    /// the Object, or `None` if it has no coordinates.
+    pub(crate) fn resolve_coordinates(&self, width: usize, height: usize) -> Option<Rect> {
+        let (left, top, right, bottom) = self.coordinates?;
+        Some(Rect::new(left.resolve(width), top.resolve(height), right.resolve(width), bottom.resolve(height)))
+    }
+
+    /// Resolves `size` into the pixel size a `source` sized image is drawn at in a `canvas`
+    /// sized Image, or `None` if the Object has no size. An axis without a percentage follows
+    /// the other one, keeping the aspect-ratio of `source`.
+    pub(crate) fn resolve_size(&self, source: (usize, usize), canvas: (usize, usize)) -> Option<(usize, usize)> {
+        let percent_of = |percent: f64, length: usize| (length as f64 * percent / 100f64).round().max(0f64) as usize;
+        let keep_aspect = |length: usize, from: usize, to: usize| {
+            if from == 0 { 0 } else { (length as f64 * to as f64 / from as f64).round() as usize }
+        };
+        match self.size? {
+            (Some(x), Some(y)) => Some((percent_of(x, canvas.0), percent_of(y, canvas.1))),
+            (Some(x), None) => {
+                let width = percent_of(x, canvas.0);
+                Some((width, keep_aspect(width, source.0, source.1)))
+            }
+            (None, Some(y)) => {
+                let height = percent_of(y, canvas.1);
+                Some((keep_aspect(height, source.1, source.0), height))
+            }
+            (None, None) => None,
+        }
+    }
+}
 
 pub struct Parser {
-    manifest: Manifest
+    manifest: Manifest,
+    assets: Assets,
 }
 
 impl Parser {
-    /// Creates a new Parser
+    /// Creates a new Parser, loading the image of every Asset of the Manifest.
     pub fn new(manifest: Manifest) -> Self {
-        Self { manifest }
+        Self::with_assets(manifest, Assets::default())
+    }
+
+    /// Creates a new Parser which looks Object `asset` ids up in `assets`. Assets of the Manifest
+    /// whose id is not in `assets` yet are loaded into it.
+    pub fn with_assets(manifest: Manifest, mut assets: Assets) -> Self {
+        for asset in manifest.assets.iter().flatten() {
+            if assets.get(&asset.id).is_none() {
+                assets.load(&asset.id, &asset.src, decode_image);
+            }
+        }
+        Self { manifest, assets }
     }
 
     /// Parses the Manifest file and return equivalent [Image].
-    pub fn parse(&self) -> Image {
-        for object in &self.manifest.objects {
+    ///
+    /// The Image is filled with the Manifest's `color`, then every Object is drawn over it in
+    /// order. Objects with a `src` or `asset` are drawn as images, Objects with a `text` in
+    /// their `font` and `color`, black by default, and other Objects with a `color` or `rgba`
+    /// as filled rectangles. An Object is drawn into the rectangle it is placed in, see
+    /// [Manifest::object_rect], or at its natural size in the top-left corner if it has none.
+    /// Text starts at the top-left corner of the rectangle and is not scaled into it.
+    pub fn parse(&self) -> Result<Image, ParseError> {
+        let manifest = &self.manifest;
+        let (width, height) = manifest.size;
+        let mut canvas = Canvas::with_size(width, height, manifest.format.clone()).clear(manifest.color);
 
+        for (object, rect) in manifest.objects.iter().zip(manifest.object_rects()?) {
+            // With a size, only the left and top coordinates are used
+            if object.size.is_none() && rect.as_ref().is_some_and(|rect| !rect.is_valid()) {
+                return Err(ParseError::InvalidCoordinates(object.name.clone()));
+            }
+            let area = match &rect {
+                Some(rect) if rect.is_valid() => *rect,
+                _ => Rect::new(0, 0, width as i32, height as i32),
+            };
+            if let (Some(text), None, None) = (&object.text, &object.src, &object.asset) {
+                canvas = self.draw_text(canvas, object, text, (area.left, area.top))?;
+                continue;
+            }
+            let Some(image) = self.object_image(object, &area)? else {
+                continue;
+            };
+
+            let size = object.resolve_size((image.width, image.height), (width, height));
+            let (image, mut origin) = match (size, rect) {
+                (Some((w, h)), rect) => {
+                    let origin = rect.map_or((0, 0), |rect| (rect.left, rect.top));
+                    (image.resize(w, h, ResizeFilter::Bilinear), origin)
+                }
+                (None, Some(rect)) => {
+                    let (source, target) = object.scale_mode.unwrap_or_default()
+                        .place(image.width, image.height, &rect);
+                    let image = image.crop(&source).resize(
+                        target.width() as usize, target.height() as usize, ResizeFilter::Bilinear
+                    );
+                    (image, (target.left, target.top))
+                }
+                (None, None) => (image, (0, 0)),
+            };
+            let image = match &object.shadow {
+                Some(shadow) => {
+                    let (image, (x, y)) = shadow.render(&image);
+                    origin = (origin.0 - x, origin.1 - y);
+                    image
+                }
+                None => image,
+            };
+            canvas = canvas.draw(image, origin);
         }
-        todo!()
+
+        Ok(canvas.into_image())
     }
-}
\ No newline at end of file
+
+    /// Draws `text` of `object` onto `canvas` with the top-left corner of its line at `origin`.
+    ///
+    /// Returns [ParseError::MissingFont] if the Object has no font or it can not be loaded.
+    fn draw_text(&self, canvas: Canvas, object: &Object, text: &str, origin: (i32, i32)) -> Result<Canvas, ParseError> {
+        let font = object.font.as_ref()
+            .and_then(|path| Font::from_file(&path.to_string_lossy(), 0))
+            .ok_or_else(|| ParseError::MissingFont(object.name.clone()))?;
+        let size = object.font_size.unwrap_or(Object::DEFAULT_FONT_SIZE);
+        Ok(canvas
+            .with_fill(object.fill_color().unwrap_or(Color::BLACK))
+            .draw(Text::new(text, &font, size), origin))
+    }
+
+    /// Returns the image drawn for `object` before it is placed, or `None` if the Object draws
+    /// nothing. Images which can not be loaded are handled by the Manifest's `on_missing`
+    /// policy, with placeholders the size of `area`.
+    fn object_image(&self, object: &Object, area: &Rect) -> Result<Option<Image>, ParseError> {
+        let asset = match &object.asset {
+            Some(id) => Some(self.asset(id)?),
+            None => None,
+        };
+        let loaded = match (&object.src, asset) {
+            (Some(src), _) => Some(load_image(src)),
+            (None, Some(image)) => Some(image.cloned()),
+            (None, None) => None,
+        };
+
+        if let Some(loaded) = loaded {
+            let mut image = match loaded {
+                Some(image) => image,
+                None => {
+                    let policy = self.manifest.on_missing.unwrap_or_default();
+                    let (width, height) = (area.width() as usize, area.height() as usize);
+                    return policy.apply(&object.name, width, height);
+                }
+            };
+            let target = match &object.recolor_from_palette {
+                Some(name) => Some(Target::named(name).ok_or_else(|| ParseError::UnknownTarget(object.name.clone()))?),
+                None => None,
+            };
+            if let (Some(target), Some(source)) = (target, asset.flatten().cloned()) {
+                let palette = PaletteBuilder::new(source).add_target(target.clone()).generate()
+                    .map_err(|error| ParseError::Palette { object: object.name.clone(), error })?;
+                // A palette without a swatch for the target leaves the image as it is
+                if let Some(swatch) = palette.get_swatch_for_target(&target) {
+                    image.tint(swatch.get_rgb());
+                }
+            }
+            return Ok(Some(image));
+        }
+
+        Ok(object.fill_color().map(|color| Image::solid(area.width() as usize, area.height() as usize, color)))
+    }
+
+    /// Returns the image of the Asset with `id`, or `None` if the Manifest has such an Asset but
+    /// its image could not be loaded.
+    ///
+    /// Returns [ParseError::UnknownAsset] if no Asset has this id.
+    fn asset(&self, id: &str) -> Result<Option<&Image>, ParseError> {
+        if let Some(image) = self.assets.get(id) {
+            return Ok(Some(image));
+        }
+        if self.manifest.assets.iter().flatten().any(|asset| asset.id == id) {
+            return Ok(None);
+        }
+        Err(ParseError::UnknownAsset(id.to_string()))
+    }
+}
+
+/// Reads and decodes the PNG or JPEG image at `path`, detecting the format from its content.
+fn load_image(path: &Path) -> Option<Image> {
+    let data = std::fs::read(path).ok()?;
+    decode_image(&data)
+}
+
+/// Decodes a PNG or JPEG image, detecting the format from its signature.
+fn decode_image(data: &[u8]) -> Option<Image> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Image::from_png_bytes(data).ok()
+    } else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+        Image::from_jpeg_bytes(data).ok()
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_missing_asset_policies() {
+    assert_eq!(MissingAssetPolicy::default(), MissingAssetPolicy::Error);
+    assert_eq!(MissingAssetPolicy::Error.apply("logo", 16, 16).err(),
+        Some(ParseError::MissingAsset("logo".to_string())));
+    assert!(MissingAssetPolicy::Skip.apply("logo", 16, 16).unwrap().is_none());
+
+    let placeholder = MissingAssetPolicy::Placeholder.apply("logo", 16, 16).unwrap().unwrap();
+    assert_eq!((placeholder.width, placeholder.height), (16, 16));
+    assert_eq!(placeholder[0..4], [255, 0, 255, 255]);
+    assert_eq!(placeholder[32..36], [0, 0, 0, 255]);
+}
+
+#[test]
+fn test_scale_modes() {
+    let area = Rect::new(0, 0, 100, 100);
+    let coverage = |rect: &Rect| (rect.width() * rect.height()) as f64 / (area.width() * area.height()) as f64;
+
+    let (src, dst) = ScaleMode::Fit.place(200, 100, &area);
+    assert_eq!((src.width(), src.height()), (200, 100));
+    assert_eq!((dst.left, dst.top, dst.right, dst.bottom), (0, 25, 100, 75));
+    assert_eq!(coverage(&dst), 0.5);
+
+    let (src, dst) = ScaleMode::Fill.place(200, 100, &area);
+    assert_eq!((src.left, src.top, src.right, src.bottom), (50, 0, 150, 100));
+    assert_eq!(coverage(&dst), 1.0);
+
+    let (src, dst) = ScaleMode::Stretch.place(200, 100, &area);
+    assert_eq!((src.width(), src.height()), (200, 100));
+    assert_eq!(coverage(&dst), 1.0);
+}
+
+#[test]
+fn test_mixed_coordinate_units() {
+    use super::Dimension;
+
+    let manifest: Manifest = toml::from_str(r#"
+        [[objects]]
+        name = "banner"
+        coordinates = [10, "25%", "50%", "80px"]
+    "#).unwrap();
+
+    let object = &manifest.objects[0];
+    assert_eq!(object.coordinates.unwrap().1, Dimension::Percent(25f64));
+    let rect = object.resolve_coordinates(200, 100).unwrap();
+    assert_eq!((rect.left, rect.top, rect.right, rect.bottom), (10, 25, 100, 80));
+
+    assert!(toml::from_str::<Manifest>(r#"
+        [[objects]]
+        name = "bad"
+        coordinates = [0, 0, "half", 10]
+    "#).is_err());
+}
+
+#[test]
+fn test_drop_shadow() {
+    let manifest: Manifest = toml::from_str(r#"
+        [[objects]]
+        name = "card"
+        shadow = { color = -16777216, offset = [3, 2] }
+    "#).unwrap();
+    let shadow = manifest.objects[0].shadow.unwrap();
+    assert_eq!((shadow.offset, shadow.blur), ((3, 2), 0));
+
+    let card = Image::solid(4, 4, Color::WHITE);
+    let (out, (x, y)) = shadow.render(&card);
+    assert_eq!((out.width, out.height, x, y), (7, 6, 0, 0));
+    let pixel = |x: usize, y: usize| out.row_bytes(y).unwrap()[x * 4..x * 4 + 4].to_vec();
+    assert_eq!(pixel(0, 0), [255, 255, 255, 255]);
+    assert_eq!(pixel(6, 5), [0, 0, 0, 255]);
+    assert_eq!(pixel(5, 1), [0, 0, 0, 0]);
+    assert_eq!(pixel(0, 5), [0, 0, 0, 0]);
+
+    let blurred = ShadowSpec { blur: 2, ..shadow }.render(&card).0;
+    let edge = blurred.row_bytes(7).unwrap()[8 * 4 + 3];
+    assert!(edge > 0 && edge < 255);
+}
+
+#[test]
+fn test_grid_layout() {
+    let manifest: Manifest = toml::from_str(r#"
+        size = [210, 110]
+        layout = { rows = 2, cols = 2, gap = 10 }
+
+        [[objects]]
+        name = "a"
+        coordinates = [0, 0, 5, 5]
+        [[objects]]
+        name = "b"
+        [[objects]]
+        name = "c"
+        [[objects]]
+        name = "d"
+        [[objects]]
+        name = "overflow"
+    "#).unwrap();
+
+    let rects: Vec<_> = (0..4)
+        .map(|i| manifest.object_rect(i).map(|r| (r.left, r.top, r.right, r.bottom)))
+        .collect();
+    assert_eq!(rects, vec![
+        Some((0, 0, 100, 50)), Some((110, 0, 210, 50)),
+        Some((0, 60, 100, 110)), Some((110, 60, 210, 110)),
+    ]);
+    assert!(manifest.object_rect(4).is_none());
+}
+
+#[test]
+fn test_relative_placement() {
+    let manifest: Manifest = toml::from_str(r#"
+        [[objects]]
+        name = "badge"
+        coordinates = [0, 0, 16, 16]
+        relative_to = "card"
+        offset = [92, -8]
+
+        [[objects]]
+        name = "card"
+        coordinates = [20, 20, 120, 80]
+
+        [[objects]]
+        name = "shadow"
+        relative_to = "card"
+        offset = [4, 4]
+    "#).unwrap();
+
+    let rects: Vec<_> = manifest.object_rects().unwrap().into_iter()
+        .map(|r| r.map(|r| (r.left, r.top, r.right, r.bottom)))
+        .collect();
+    assert_eq!(rects, vec![
+        Some((112, 12, 128, 28)), Some((20, 20, 120, 80)), Some((24, 24, 124, 84)),
+    ]);
+
+    let cyclic: Manifest = toml::from_str(r#"
+        [[objects]]
+        name = "a"
+        relative_to = "b"
+        [[objects]]
+        name = "b"
+        relative_to = "a"
+    "#).unwrap();
+    assert_eq!(cyclic.object_rects().err(), Some(ParseError::PlacementCycle("a".to_string())));
+
+    let dangling: Manifest = toml::from_str(r#"
+        [[objects]]
+        name = "c"
+        relative_to = "missing"
+    "#).unwrap();
+    assert_eq!(dangling.object_rects().err(), Some(ParseError::UnknownObject("missing".to_string())));
+}
+
+#[test]
+fn test_parse() {
+    let dir = std::env::temp_dir().join(format!("naqsh-parse-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let logo = dir.join("logo.png");
+    std::fs::write(&logo, Image::solid(2, 2, Color::rgb(0, 0, 255)).to_png_bytes().unwrap()).unwrap();
+
+    let font = dir.join("font.ttf");
+    std::fs::write(&font, crate::font::TEST_FONT).unwrap();
+
+    let manifest: Manifest = toml::from_str(&format!(r#"
+        size = [8, 12]
+        color = -1
+        assets = [{{ id = "logo", src = "{}" }}]
+
+        [[objects]]
+        name = "bar"
+        color = -65536
+        coordinates = [0, 0, 8, 1]
+
+        [[objects]]
+        name = "logo"
+        asset = "logo"
+        coordinates = [4, 0, 8, 4]
+
+        [[objects]]
+        name = "caption"
+        text = "hello"
+        font = "{}"
+        font_size = 8.0
+        color = -16777216
+        coordinates = [0, 4, 8, 12]
+    "#, logo.display(), font.display())).unwrap();
+    let image = Parser::new(manifest).parse().unwrap();
+    assert_eq!((image.width, image.height), (8, 12));
+    let pixel = |x: usize, y: usize| image.row_bytes(y).unwrap()[x * 4..x * 4 + 4].to_vec();
+    assert_eq!(pixel(0, 0), [255, 0, 0, 255]);
+    assert_eq!(pixel(0, 3), [255, 255, 255, 255]);
+    assert_eq!(pixel(4, 0), [0, 0, 255, 255]);
+    assert_eq!(pixel(7, 3), [0, 0, 255, 255]);
+    // The caption is inked in black below its origin
+    let inked = |y: usize| (0..8).any(|x| pixel(x, y)[0] < 128);
+    assert!((4..12).any(inked));
+    assert!(!(1..4).any(|y| (0..4).any(|x| pixel(x, y)[0] < 128)));
+
+    let missing = |policy: &str| toml::from_str::<Manifest>(&format!(r#"
+        size = [16, 16]
+        on_missing = "{policy}"
+
+        [[objects]]
+        name = "gone"
+        src = "{}"
+    "#, dir.join("missing.png").display())).unwrap();
+    assert_eq!(Parser::new(missing("error")).parse().err(), Some(ParseError::MissingAsset("gone".to_string())));
+    assert_eq!(Parser::new(missing("skip")).parse().unwrap().row_bytes(0).unwrap(), [0; 64]);
+    let placeholder = Parser::new(missing("placeholder")).parse().unwrap();
+    let pixel = |x: usize, y: usize| placeholder.row_bytes(y).unwrap()[x * 4..x * 4 + 4].to_vec();
+    // A magenta and black checkerboard of 8 pixel cells covers the Object
+    assert_eq!(pixel(0, 0), [255, 0, 255, 255]);
+    assert_eq!(pixel(8, 0), [0, 0, 0, 255]);
+    assert_eq!(pixel(0, 8), [0, 0, 0, 255]);
+    assert_eq!(pixel(15, 15), [255, 0, 255, 255]);
+
+    let inverted: Manifest = toml::from_str(r#"
+        [[objects]]
+        name = "inverted"
+        color = -1
+        coordinates = [10, 10, 0, 0]
+    "#).unwrap();
+    assert_eq!(Parser::new(inverted).parse().err(), Some(ParseError::InvalidCoordinates("inverted".to_string())));
+
+    let fontless: Manifest = toml::from_str(r#"
+        [[objects]]
+        name = "caption"
+        text = "hello"
+    "#).unwrap();
+    assert_eq!(Parser::new(fontless).parse().err(), Some(ParseError::MissingFont("caption".to_string())));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_parse_resolves_assets() {
+    let manifest = || toml::from_str::<Manifest>(r#"
+        size = [2, 2]
+
+        [[objects]]
+        name = "icon"
+        asset = "icon"
+    "#).unwrap();
+    assert_eq!(Parser::new(manifest()).parse().err(), Some(ParseError::UnknownAsset("icon".to_string())));
+
+    let mut assets = Assets::default();
+    assets.insert("icon", Image::solid(1, 1, Color::rgb(0, 255, 0)));
+    let image = Parser::with_assets(manifest(), assets).parse().unwrap();
+    assert_eq!(image.row_bytes(0).unwrap()[..8], [0, 255, 0, 255, 0, 0, 0, 0]);
+
+    // A declared Asset whose file is missing goes through the missing asset policy instead
+    let declared: Manifest = toml::from_str(r#"
+        size = [2, 2]
+        assets = [{ id = "icon", src = "/nonexistent/icon.png" }]
+
+        [[objects]]
+        name = "icon"
+        asset = "icon"
+    "#).unwrap();
+    assert_eq!(Parser::new(declared).parse().err(), Some(ParseError::MissingAsset("icon".to_string())));
+}
+
+#[cfg(test)]
+/// Returns an Object with `size`. TOML has no null, so one-sided sizes can not be written in it.
+fn sized_object(size: (Option<f64>, Option<f64>)) -> Object {
+    let manifest: Manifest = toml::from_str(r#"
+        [[objects]]
+        name = "logo"
+    "#).unwrap();
+    let object = manifest.objects.into_iter().next().unwrap();
+    Object { size: Some(size), ..object }
+}
+
+#[test]
+fn test_size_both_axes() {
+    let object = sized_object((Some(50.0), Some(25.0)));
+    assert_eq!(object.resolve_size((10, 10), (200, 100)), Some((100, 25)));
+
+    let manifest: Manifest = toml::from_str(r#"
+        size = [8, 8]
+
+        [[objects]]
+        name = "bar"
+        color = -1
+        coordinates = [2, 4, 0, 0]
+        size = [50.0, 25.0]
+    "#).unwrap();
+    let image = Parser::new(manifest).parse().unwrap();
+    let opaque: Vec<_> = (0..8).map(|y| image.row_bytes(y).unwrap().chunks_exact(4).filter(|p| p[3] == 255).count()).collect();
+    assert_eq!(opaque, vec![0, 0, 0, 0, 4, 4, 0, 0]);
+}
+
+#[test]
+fn test_size_width_only() {
+    let object = sized_object((Some(50.0), None));
+    assert_eq!(object.resolve_size((40, 20), (200, 100)), Some((100, 50)));
+    assert_eq!(object.resolve_size((0, 0), (200, 100)), Some((100, 0)));
+}
+
+#[test]
+fn test_size_height_only() {
+    let object = sized_object((None, Some(10.0)));
+    assert_eq!(object.resolve_size((40, 20), (200, 100)), Some((20, 10)));
+    assert_eq!(sized_object((None, None)).resolve_size((40, 20), (200, 100)), None);
+}
+
+#[test]
+fn test_coordinates_placement() {
+    let dir = std::env::temp_dir().join(format!("naqsh-coordinates-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let tile = dir.join("tile.png");
+    std::fs::write(&tile, Image::solid(2, 2, Color::rgb(0, 0, 255)).to_png_bytes().unwrap()).unwrap();
+
+    let manifest: Manifest = toml::from_str(&format!(r#"
+        size = [8, 4]
+
+        [[objects]]
+        name = "stretched"
+        src = "{0}"
+        coordinates = ["75%", "50%", "100%", "100%"]
+        scale_mode = "stretch"
+
+        [[objects]]
+        name = "off-canvas"
+        src = "{0}"
+        coordinates = [-3, -3, 1, 1]
+
+        [[objects]]
+        name = "past the edge"
+        src = "{0}"
+        coordinates = [20, 20, 40, 40]
+    "#, tile.display())).unwrap();
+    let image = Parser::new(manifest).parse().unwrap();
+
+    let opaque = |y: usize| -> Vec<usize> {
+        image.row_bytes(y).unwrap().chunks_exact(4).enumerate()
+            .filter(|(_, p)| p[3] == 255).map(|(x, _)| x).collect()
+    };
+    assert_eq!(opaque(0), vec![0]);
+    assert!(opaque(1).is_empty());
+    assert_eq!(opaque(2), vec![6, 7]);
+    assert_eq!(opaque(3), vec![6, 7]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_recolor_from_palette() {
+    let dir = std::env::temp_dir().join(format!("naqsh-recolor-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (photo, logo) = (dir.join("photo.png"), dir.join("logo.png"));
+    let source = Image::solid(8, 8, Color::rgb(220, 30, 40));
+    std::fs::write(&photo, source.to_png_bytes().unwrap()).unwrap();
+    std::fs::write(&logo, Image::solid(2, 2, Color::WHITE).to_png_bytes().unwrap()).unwrap();
+
+    let manifest = |target: &str| toml::from_str::<Manifest>(&format!(r#"
+        size = [2, 2]
+        assets = [{{ id = "photo", src = "{}" }}]
+
+        [[objects]]
+        name = "logo"
+        src = "{}"
+        asset = "photo"
+        recolor_from_palette = "{target}"
+    "#, photo.display(), logo.display())).unwrap();
+
+    // The logo takes the vibrant swatch of the photo
+    let palette = PaletteBuilder::new(source).generate().unwrap();
+    let vibrant = palette.get_swatch_for_name("vibrant").unwrap().get_rgb();
+    let image = Parser::new(manifest("vibrant")).parse().unwrap();
+    assert_eq!(image.row_bytes(1).unwrap()[4..8], [vibrant.red(), vibrant.green(), vibrant.blue(), 255]);
+    assert_ne!(image.row_bytes(1).unwrap()[4..8], [255, 255, 255, 255]);
+
+    assert_eq!(Parser::new(manifest("neon")).parse().err(), Some(ParseError::UnknownTarget("logo".to_string())));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,24 +1,26 @@
 #![allow(dead_code)]
 /// SparseBooleanArrays map integers to booleans.
 /// Unlike a normal array of booleans
-/// there can be gaps in the indices.  It is intended to be more memory efficient
-/// than using a HashMap to map Integers to Booleans, both because it avoids
-/// auto-boxing keys and values and its data structure doesn't rely on an extra entry object
-/// for each mapping.
+/// there can be gaps in the indices. The keys and values are kept in two sorted arrays, which
+/// uses less memory than a HashMap for small maps as there is no per-entry overhead.
 ///
 /// <p>Note that this container keeps its mappings in an array data structure,
 /// using a binary search to find keys.  The implementation is not intended to be appropriate for
-/// data structures
-/// that may contain large numbers of items.  It is generally slower than a traditional
+/// data structures that may contain large numbers of items.  It is generally slower than a
 /// HashMap, since lookups require a binary search and adds and removes require inserting
-/// and deleting entries in the array.  For containers holding up to hundreds of items,
-/// the performance difference is not significant, less than 50%.</p>
+/// and deleting entries in the array.</p>
+///
+/// <p>Lookups are O(log n), while [put](SparseBooleanArray::put) of a new key and
+/// [delete](SparseBooleanArray::delete) are O(n) as they shift the entries after the
+/// affected index. Removal is not deferred, so churning through many thousands of keys is
+/// much slower than with a HashMap; use one for such workloads.</p>
 ///
 /// <p>It is possible to iterate over the items in this container using
-/// {@link #keyAt(int)} and {@link #valueAt(int)}. Iterating over the keys using
-/// <code>keyAt(int)</code> with ascending values of the index will return the
+/// [key_at](SparseBooleanArray::key_at) and [value_at](SparseBooleanArray::value_at), or with
+/// [iter](SparseBooleanArray::iter). Iterating over the keys using
+/// <code>key_at</code> with ascending values of the index will return the
 /// keys in ascending order, or the values corresponding to the keys in ascending
-/// order in the case of <code>valueAt(int)</code>.</p>
+/// order in the case of <code>value_at</code>.</p>
 pub struct SparseBooleanArray {
     m_keys: Vec<i32>,
     m_values: Vec<bool>,
@@ -57,17 +59,24 @@ impl SparseBooleanArray {
         }
     }
 
-    /// Removes the mapping from the specified key, if there was any.
+    /// Removes the mapping from the specified key, if there was any. This takes O(n) time,
+    /// see [remove_at](Self::remove_at).
     pub fn delete(&mut self, key: i32) {
-        let i = self.m_keys.binary_search(&key);
-
-        if let Ok(i) = i {
-            self.m_keys.remove(i);
-            self.m_values.remove(i);
-            self.m_size -= 1;
+        if let Ok(i) = self.m_keys.binary_search(&key) {
+            self.remove_at(i);
         }
     }
 
+    /// Removes the mapping at the given index, in ascending key order. The following
+    /// mappings are shifted down, so this takes O(n) time, except for the last index.
+    ///
+    /// Panics if `index` is out of range.
+    pub fn remove_at(&mut self, index: usize) {
+        self.m_keys.remove(index);
+        self.m_values.remove(index);
+        self.m_size -= 1;
+    }
+
     /// Adds a mapping from the specified key to the specified value,
     /// replacing the previous mapping from the specified key if there
     /// was one.
@@ -86,6 +95,47 @@ impl SparseBooleanArray {
         }
     }
 
+    /// Returns the number of key-value mappings that this SparseBooleanArray
+    /// currently stores.
+    pub fn size(&self) -> usize {
+        self.m_size
+    }
+
+    /// Given an index in the range <code>0...size()-1</code>, returns
+    /// the key from the <code>index</code>th key-value mapping that this
+    /// SparseBooleanArray stores. Keys are in ascending order.
+    ///
+    /// Panics if `index` is out of range.
+    pub fn key_at(&self, index: usize) -> i32 {
+        self.m_keys[..self.m_size][index]
+    }
+
+    /// Given an index in the range <code>0...size()-1</code>, returns
+    /// the value from the <code>index</code>th key-value mapping that this
+    /// SparseBooleanArray stores, in ascending order of the keys.
+    ///
+    /// Panics if `index` is out of range.
+    pub fn value_at(&self, index: usize) -> bool {
+        self.m_values[..self.m_size][index]
+    }
+
+    /// Returns an iterator over the `(key, value)` mappings in ascending key order.
+    pub fn iter(&self) -> impl Iterator<Item = (i32, bool)> + '_ {
+        self.m_keys[..self.m_size].iter().copied().zip(self.m_values[..self.m_size].iter().copied())
+    }
+
+    /// Directly sets the value at a particular index, without a key lookup. Indices are in
+    /// ascending key order.
+    ///
+    /// Returns `false` if `index` is out of range.
+    pub fn set_value_at(&mut self, index: usize, value: bool) -> bool {
+        if index >= self.m_size {
+            return false;
+        }
+        self.m_values[index] = value;
+        true
+    }
+
     /// Removes all key-value mappings from this SparseBooleanArray.
     pub fn clear(&mut self) {
         self.m_keys.clear();
@@ -111,4 +161,52 @@ impl Default for SparseBooleanArray {
     fn default() -> Self {
        Self::new(0)
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_set_value_at() {
+    let mut array = SparseBooleanArray::default();
+    array.put(30, true);
+    array.put(10, false);
+    array.put(20, true);
+
+    // Keys are sorted, so index 0 holds key 10
+    assert!(array.set_value_at(0, true));
+    assert!(array.get_or_false(10));
+    assert!(array.set_value_at(2, false));
+    assert!(!array.get(30, true));
+    assert!(!array.set_value_at(3, true));
+}
+
+#[test]
+fn test_iteration() {
+    let mut array = SparseBooleanArray::default();
+    assert_eq!(array.size(), 0);
+    assert_eq!(array.iter().next(), None);
+
+    array.put(30, true);
+    array.put(-5, false);
+    array.append(40, true);
+    array.put(10, true);
+    array.delete(30);
+
+    assert_eq!(array.size(), 3);
+    assert_eq!((array.key_at(0), array.value_at(0)), (-5, false));
+    assert_eq!((array.key_at(2), array.value_at(2)), (40, true));
+    assert_eq!(array.iter().collect::<Vec<_>>(), [(-5, false), (10, true), (40, true)]);
+}
+
+#[test]
+fn test_remove_at() {
+    let mut array = SparseBooleanArray::default();
+    array.put(1, true);
+    array.put(2, false);
+    array.put(3, true);
+
+    array.remove_at(1);
+    assert_eq!(array.iter().collect::<Vec<_>>(), [(1, true), (3, true)]);
+    array.remove_at(1);
+    array.delete(1);
+    array.delete(7);
+    assert_eq!(array.size(), 0);
+}